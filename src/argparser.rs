@@ -1,2 +1,5 @@
+#[cfg(feature = "cli")]
 pub mod arguments;
+#[cfg(all(feature = "cli", feature = "cache"))]
+pub mod config;
 pub mod settings;
@@ -1,93 +1,465 @@
-use crate::types::{AsrMethod, LatitudeMethod, PrayerMethod};
+use crate::{
+    source::{default_sources, Source},
+    types::{
+        AsrMethod, Coordinates, HijriCalendar, LatitudeMethod, MethodParameters, PrayerMethod,
+        Twilight, UmmahResult,
+    },
+};
 
-use clap::Parser;
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+use clap::{Args, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 
-use super::settings::{CalculationMethods, Location, PrayerSettings};
+use std::ops::RangeInclusive;
+
+use super::settings::{CalculationMethods, Location, PrayerOffsets, PrayerSettings};
+
+/// City used when neither a city nor coordinates are given
+static DEFAULT_CITY: &str = "bath";
+
+/// Country used when none is given
+static DEFAULT_COUNTRY: &str = "uk";
 
 /// Gets prayer times from www.salahtimes.com/uk
-#[derive(Parser, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Parser, Debug, PartialEq, Serialize, Deserialize)]
 #[clap(author, version, about, long_about = None)]
 pub struct PrayerArguments {
     /// Latitude method
-    #[clap(long, arg_enum, default_value = "one-seventh")]
+    #[clap(long, global = true, arg_enum, default_value = "one-seventh")]
     latitude_method: LatitudeMethod,
 
+    /// Latitude used by the nearest-latitude method, in degrees
+    #[clap(long, global = true, default_value = "48")]
+    nearest_latitude: f64,
+
     /// Source of Prayer calculation
-    #[clap(long, arg_enum, default_value = "mwl")]
+    #[clap(long, global = true, arg_enum, default_value = "mwl")]
     prayer_method: PrayerMethod,
 
+    /// Fajr angle for the custom prayer method
+    #[clap(long, global = true, required_if_eq("prayer-method", "custom"))]
+    fajr_angle: Option<f64>,
+
+    /// Isha angle for the custom prayer method
+    /// If not set, the Fajr angle is used
+    #[clap(long, global = true, conflicts_with = "isha-minutes")]
+    isha_angle: Option<f64>,
+
+    /// Minutes after Maghrib for Isha in the custom prayer method
+    #[clap(long, global = true)]
+    isha_minutes: Option<i64>,
+
+    /// Maghrib angle for the custom prayer method
+    #[clap(long, global = true, conflicts_with = "maghrib-minutes")]
+    maghrib_angle: Option<f64>,
+
+    /// Minutes after sunset for Maghrib in the custom prayer method
+    #[clap(long, global = true)]
+    maghrib_minutes: Option<i64>,
+
     /// Asr time method
-    #[clap(long, arg_enum, default_value = "shafi")]
+    #[clap(long, global = true, arg_enum, default_value = "shafi")]
     asr_method: AsrMethod,
 
+    /// Country of the location, uk if not set
+    ///
+    /// Also limits `search` to the country, which otherwise searches every country.
+    #[clap(long, global = true)]
+    country: Option<String>,
+
+    /// City, as named by www.salahtimes.com
+    ///
+    /// Defaults to the nearest city to the coordinates, or Bath without coordinates.
+    #[clap(long, global = true)]
+    city: Option<String>,
+
+    /// Latitude of the location, in degrees north
+    #[clap(
+        long,
+        global = true,
+        allow_hyphen_values = true,
+        requires = "longitude"
+    )]
+    latitude: Option<f64>,
+
+    /// Longitude of the location, in degrees east
+    #[clap(long, global = true, allow_hyphen_values = true, requires = "latitude")]
+    longitude: Option<f64>,
+
+    /// Height of the location above sea level, in metres
+    #[clap(long, global = true, allow_hyphen_values = true)]
+    elevation: Option<f64>,
+
+    /// Atmospheric pressure at the location, in millibars
+    #[clap(long, global = true)]
+    pressure: Option<f64>,
+
+    /// Air temperature at the location, in degrees Celsius
+    #[clap(long, global = true, allow_hyphen_values = true)]
+    temperature: Option<f64>,
+
+    /// Ignore elevation and weather, matching the website's sea level times
+    #[clap(long, global = true)]
+    sea_level: bool,
+
+    /// Angle of magnetic north east of true north, in degrees
+    #[clap(long, global = true, allow_hyphen_values = true)]
+    magnetic_declination: Option<f64>,
+
+    /// IANA time zone of the location, such as Europe/London
+    #[clap(long, global = true)]
+    time_zone: Option<Tz>,
+
+    /// Show the direction and distance to the Kaaba
+    #[clap(long, global = true)]
+    qibla: bool,
+
+    /// Minutes added to Fajr, negative to bring it forward
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    fajr_offset: i64,
+
+    /// Minutes added to Dhuhr, negative to bring it forward
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    dhuhr_offset: i64,
+
+    /// Minutes added to Asr, negative to bring it forward
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    asr_offset: i64,
+
+    /// Minutes added to Maghrib, negative to bring it forward
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    maghrib_offset: i64,
+
+    /// Minutes added to Isha, negative to bring it forward
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    isha_offset: i64,
+
+    /// Minutes before Fajr at which Imsak begins
+    #[clap(long, global = true, default_value = "10")]
+    imsak_minutes: i64,
+
+    /// Minutes after sunrise at which Duha begins
+    #[clap(long, global = true, default_value = "15")]
+    duha_minutes: i64,
+
+    /// Calendar used for Hijri dates
+    #[clap(long, global = true, arg_enum, default_value = "umm-al-qura")]
+    hijri_calendar: HijriCalendar,
+
+    /// Days added to the Hijri date, to follow a local moon sighting
+    #[clap(long, global = true, allow_hyphen_values = true, default_value = "0")]
+    hijri_adjustment: i64,
+
+    /// Minutes before Fajr at which Suhoor ends, instead of at Imsak
+    #[clap(long, global = true)]
+    suhoor_margin: Option<i64>,
+
+    /// Minutes after Isha at which Taraweeh begins
+    #[clap(long, global = true, default_value = "15")]
+    taraweeh_minutes: i64,
+
+    /// Calculate times locally instead of downloading them
+    #[clap(long, global = true, conflicts_with = "source")]
+    offline: bool,
+
+    /// Source of the times, repeated to fall back on later sources when the
+    /// earlier ones fail or do not support the location and methods
+    ///
+    /// One of salahtimes[:<url>], calculation, csv:<path> or json[:<url>].
+    /// Defaults to www.salahtimes.com, then calculation.
+    #[clap(long, global = true)]
+    source: Vec<Source>,
+
+    /// Download next month's times in the last days of the current month
+    #[clap(long, global = true, conflicts_with = "offline")]
+    prefetch: bool,
+
+    /// Proxy for downloads, such as `http://proxy.example:8080`
+    ///
+    /// Defaults to the HTTP_PROXY and HTTPS_PROXY environment variables.
+    #[clap(long, global = true, conflicts_with = "offline")]
+    proxy: Option<String>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// What to do
+#[derive(Subcommand, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    #[clap(flatten)]
+    Times(TimesCommand),
+
+    /// Search the gazetteer for places, in the country if one is given
+    Search { query: String },
+
+    /// Inspect or tidy the cache of downloaded timetables
+    #[clap(subcommand)]
+    Cache(CacheCommand),
+
+    /// Show or change the defaults for the arguments
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// What to do with the times, which need the settings
+#[derive(Subcommand, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TimesCommand {
+    /// Show today's times
+    Today,
+
+    /// Show the next prayer and the time left until it
+    Next,
+
+    /// Show the times of a month, the current one by default
+    Month {
+        #[clap(flatten)]
+        month: MonthArgs,
+
+        /// Show the Suhoor and Iftar times of the current or next Ramadan instead
+        #[clap(long, conflicts_with = "month")]
+        ramadan: bool,
+    },
+
+    /// Show the times of any range of dates
+    Range(RangeArgs),
+
+    /// Export the times of a month or range of dates to an HTML file
+    Export(ExportArgs),
+}
+
+/// Month of the times, the current one if not set
+#[derive(Args, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthArgs {
     /// The month to pull the timetable from
     #[clap(long)]
     month: Option<u32>,
 
-    /// Country
-    #[clap(long, default_value = "uk")]
-    country: String,
+    /// The year of the month to pull the timetable from
+    /// If not set, uses the current year
+    #[clap(long, requires = "month")]
+    year: Option<i32>,
+}
 
-    /// City
-    #[clap(long, default_value = "bath")]
-    city: String,
+impl MonthArgs {
+    /// First day of the requested month, in the year of the given date if no year was set
+    pub fn custom_month(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.month.and_then(|month| {
+            NaiveDate::from_ymd_opt(self.year.unwrap_or_else(|| today.year()), month, 1)
+        })
+    }
+}
 
-    /// Get today's times
-    #[clap(short, long)]
-    today: bool,
+/// Dates of the times
+#[derive(Args, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeArgs {
+    /// First date of the timetable, such as 2027-01-01
+    #[clap(long)]
+    from: NaiveDate,
 
-    /// Exports times to HTML file
+    /// Last date of the timetable, such as 2027-12-31
     #[clap(long)]
-    export: bool,
+    to: NaiveDate,
+}
+
+impl RangeArgs {
+    /// Dates from `--from` to `--to`
+    pub fn date_range(&self) -> RangeInclusive<NaiveDate> {
+        self.from..=self.to
+    }
+}
+
+/// Times to export and how
+#[derive(Args, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportArgs {
+    #[clap(flatten)]
+    month: MonthArgs,
+
+    /// First date of the timetable, such as 2027-01-01, instead of a month
+    #[clap(long, requires = "to", conflicts_with = "month")]
+    from: Option<NaiveDate>,
+
+    /// Last date of the timetable, such as 2027-12-31
+    #[clap(long, requires = "from")]
+    to: Option<NaiveDate>,
+
+    /// Export the Suhoor and Iftar times of the current or next Ramadan instead
+    #[clap(long, conflicts_with_all = &["month", "from"])]
+    ramadan: bool,
 
     /// Generate default CSS for HTML file
     /// If not set, generates template CSS for custom editing
     #[clap(long)]
     generate_css: bool,
+}
 
-    /// Clear cache
-    #[clap(long)]
-    clear_cache: bool,
+impl ExportArgs {
+    /// First day of the requested month, in the year of the given date if no year was set
+    pub fn custom_month(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.month.custom_month(today)
+    }
+
+    /// Dates from `--from` to `--to`, if both were set
+    pub fn date_range(&self) -> Option<RangeInclusive<NaiveDate>> {
+        self.from.zip(self.to).map(|(from, to)| from..=to)
+    }
+
+    /// Flag for exporting the times for Ramadan
+    pub fn is_ramadan(&self) -> bool {
+        self.ramadan
+    }
+
+    /// Flag for generating default CSS file for timetable
+    pub fn generate_default_css(&self) -> bool {
+        self.generate_css
+    }
+}
+
+/// Ways to inspect or tidy the cache
+#[derive(Subcommand, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CacheCommand {
+    /// List the cached timetables, most recently used first
+    List,
+
+    /// Remove everything cached and exported
+    Clear,
+
+    /// Remove the timetables not used for a while
+    Prune {
+        /// Days a timetable is kept after it was last used
+        #[clap(long, default_value = "180")]
+        days: i64,
+    },
+}
+
+/// Ways to show or change the defaults
+#[derive(Subcommand, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConfigCommand {
+    /// Show the defaults and the settings they give
+    Show,
+
+    /// Set the default for an argument, used when it is not given
+    ///
+    /// Arguments are named without their dashes, such as `city` or `prayer-method`.
+    /// Flags are set with `true` or `false`.
+    Set { key: String, value: String },
 }
 
 impl PrayerArguments {
     /// Get prayer calculation settings
-    pub fn settings(&self) -> PrayerSettings {
-        PrayerSettings::new(
+    ///
+    /// Fails if the city is not in the [gazetteer](crate::gazetteer).
+    pub fn settings(&self) -> UmmahResult<PrayerSettings> {
+        Ok(PrayerSettings::new(
             CalculationMethods {
                 latitude: self.latitude_method,
                 prayer: self.prayer_method,
                 asr: self.asr_method,
+                custom: self.custom_method(),
+                nearest_latitude: self.nearest_latitude,
+                sea_level: self.sea_level,
             },
             Location {
-                country: self.country.clone(),
-                city: self.city.clone(),
-            },
+                country: self
+                    .country
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_COUNTRY.to_owned()),
+                city: self
+                    .city
+                    .clone()
+                    .or_else(|| self.latitude.is_none().then(|| DEFAULT_CITY.to_owned())),
+                coordinates: self
+                    .latitude
+                    .zip(self.longitude)
+                    .map(|(latitude, longitude)| Coordinates {
+                        latitude,
+                        longitude,
+                    }),
+                elevation: self.elevation,
+                pressure: self.pressure,
+                temperature: self.temperature,
+                magnetic_declination: self.magnetic_declination,
+                time_zone: self.time_zone,
+            }
+            .resolve()?,
         )
+        .with_offsets(PrayerOffsets {
+            fajr: self.fajr_offset,
+            dhuhr: self.dhuhr_offset,
+            asr: self.asr_offset,
+            maghrib: self.maghrib_offset,
+            isha: self.isha_offset,
+        })
+        .with_imsak_minutes(self.imsak_minutes)
+        .with_duha_minutes(self.duha_minutes)
+        .with_hijri_calendar(self.hijri_calendar, self.hijri_adjustment)
+        .with_ramadan(self.suhoor_margin, self.taraweeh_minutes)
+        .with_prefetch(self.prefetch)
+        .with_sources(self.sources())
+        .with_proxy(self.proxy.clone()))
     }
 
-    /// Clears cache
-    pub fn clear_cache(&self) -> bool {
-        self.clear_cache
+    /// Sources chosen on the command line, or calculation alone when offline
+    fn sources(&self) -> Vec<Source> {
+        match (self.offline, self.source.is_empty()) {
+            (true, _) => vec![Source::Calculation],
+            (false, true) => default_sources(),
+            (false, false) => self.source.clone(),
+        }
     }
 
-    /// Flag for selecting only today's prayer times
-    pub fn is_today_only(&self) -> bool {
-        self.today
+    /// Angles and intervals for the custom prayer method
+    fn custom_method(&self) -> Option<MethodParameters> {
+        if self.prayer_method != PrayerMethod::Custom {
+            return None;
+        }
+
+        let fajr = self.fajr_angle?;
+
+        let isha = match (self.isha_minutes, self.isha_angle) {
+            (Some(minutes), _) => Twilight::Minutes(minutes),
+            (None, angle) => Twilight::Angle(angle.unwrap_or(fajr)),
+        };
+
+        let maghrib = match (self.maghrib_minutes, self.maghrib_angle) {
+            (Some(minutes), _) => Twilight::Minutes(minutes),
+            (None, Some(angle)) => Twilight::Angle(angle),
+            (None, None) => Twilight::Minutes(0),
+        };
+
+        Some(MethodParameters {
+            maghrib,
+            isha,
+            ..MethodParameters::angles(fajr, fajr)
+        })
     }
 
-    /// Flag for exporting timetable to HTML file
-    pub fn export_enabled(&self) -> bool {
-        self.export
+    /// What to do with the times, showing the current month if not set
+    pub fn command(&self) -> Command {
+        self.command
+            .clone()
+            .unwrap_or(Command::Times(TimesCommand::Month {
+                month: MonthArgs {
+                    month: None,
+                    year: None,
+                },
+                ramadan: false,
+            }))
     }
 
-    /// Flag for generating default CSS file for timetable
-    pub fn generate_default_css(&self) -> bool {
-        self.generate_css
+    /// Country given on the command line, if any
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+
+    /// Flag for calculating times without network access
+    pub fn is_offline(&self) -> bool {
+        self.offline
     }
 
-    pub fn month(&self) -> Option<u32> {
-        self.month
+    /// Flag for showing the Qibla direction
+    pub fn show_qibla(&self) -> bool {
+        self.qibla
     }
 }
@@ -0,0 +1,230 @@
+//! Defaults for the command line arguments, kept in the user's config directory
+//!
+//! The defaults are used for the arguments not given on the command line, so a
+//! location or prayer method need only be set once.
+
+use crate::{
+    core::fs::{get_config_filepath, open_file, write_serialized_file},
+    types::{UmmahError, UmmahResult},
+};
+
+use clap::{CommandFactory, Parser};
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, ffi::OsString, path::PathBuf};
+
+use super::arguments::PrayerArguments;
+
+static CONFIG: &str = "config.yaml";
+
+/// Defaults for the arguments, by their long names without dashes, such as `city`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    defaults: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Loads the defaults, or none if they were never set
+    pub fn load() -> UmmahResult<Self> {
+        let path = Config::path();
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        let file = open_file(&path)?;
+        serde_yaml::from_reader(file).map_err(UmmahError::yaml(path))
+    }
+
+    /// Saves the defaults
+    pub fn save(&self) -> UmmahResult<()> {
+        write_serialized_file(&get_config_filepath(), &PathBuf::from(CONFIG), self)
+    }
+
+    /// Path of the file holding the defaults
+    pub fn path() -> PathBuf {
+        get_config_filepath().join(CONFIG)
+    }
+
+    /// Iterates over the defaults by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.defaults
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Sets the default for an argument, checking it against the other defaults
+    ///
+    /// Flags are set with `true` or `false`. Fails with [UmmahError::Config] if there is
+    /// no such argument or the value is not valid for it.
+    pub fn set(&mut self, key: &str, value: &str) -> UmmahResult<()> {
+        let key = key.trim_start_matches('-');
+        let config_error = |reason: String| UmmahError::Config {
+            key: key.to_owned(),
+            reason,
+        };
+
+        let takes_value =
+            takes_value(key).ok_or_else(|| config_error("no such argument".into()))?;
+        if !takes_value && value != "true" && value != "false" {
+            return Err(config_error("expected true or false".into()));
+        }
+
+        let mut config = self.clone();
+        config.defaults.insert(key.to_owned(), value.to_owned());
+
+        PrayerArguments::try_parse_from(config.apply([OsString::from("ummah")]))
+            .map_err(|error| {
+                let message = error.to_string();
+                let reason = message.lines().next().unwrap_or_default();
+                config_error(reason.trim_start_matches("error: ").to_owned())
+            })?
+            .settings()?;
+
+        *self = config;
+
+        Ok(())
+    }
+
+    /// Puts the defaults for the arguments not given after the program name
+    pub fn apply<I: IntoIterator<Item = OsString>>(&self, args: I) -> Vec<OsString> {
+        let mut args = args.into_iter();
+        let program = args.next();
+        let given = args.collect::<Vec<_>>();
+
+        let is_given = |key: &str| {
+            let flag = format!("--{}", key);
+            given.iter().any(|arg| {
+                let arg = arg.to_string_lossy();
+                arg == flag || arg.starts_with(&format!("{}=", flag))
+            })
+        };
+
+        let mut defaults = Vec::new();
+        for (key, value) in self.iter().filter(|(key, _)| !is_given(key)) {
+            match takes_value(key) {
+                Some(true) => defaults.extend([format!("--{}", key).into(), value.into()]),
+                Some(false) if value == "true" => defaults.push(format!("--{}", key).into()),
+                _ => {}
+            }
+        }
+
+        program.into_iter().chain(defaults).chain(given).collect()
+    }
+}
+
+/// Checks if the argument takes a value or is a flag, if there is such an argument
+fn takes_value(key: &str) -> Option<bool> {
+    <PrayerArguments as CommandFactory>::command()
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(key))
+        .map(|arg| arg.is_takes_value_set())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{argparser::settings::PrayerSettings, types::PrayerMethod};
+
+    fn config(defaults: &[(&str, &str)]) -> Config {
+        Config {
+            defaults: defaults
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn settings(config: &Config, given: &[&str]) -> PrayerSettings {
+        PrayerArguments::try_parse_from(config.apply(args(given)))
+            .unwrap()
+            .settings()
+            .unwrap()
+    }
+
+    #[test]
+    fn puts_the_defaults_before_the_given_arguments() {
+        let config = config(&[
+            ("city", "leeds"),
+            ("offline", "false"),
+            ("sea-level", "true"),
+        ]);
+
+        assert_eq!(
+            config.apply(args(&["ummah", "today"])),
+            args(&["ummah", "--city", "leeds", "--sea-level", "today"])
+        );
+    }
+
+    #[test]
+    fn lets_given_arguments_override_the_defaults() {
+        let config = config(&[
+            ("city", "leeds"),
+            ("prayer-method", "isna"),
+            ("imsak-minutes", "20"),
+        ]);
+
+        for given in [
+            ["ummah", "--city", "york", "today"].as_slice(),
+            ["ummah", "today", "--city=york"].as_slice(),
+        ] {
+            let applied = config.apply(args(given));
+            let cities = applied
+                .iter()
+                .filter(|arg| arg.to_string_lossy().starts_with("--city"))
+                .count();
+            assert_eq!(cities, 1, "{:?}", applied);
+
+            let settings = settings(&config, given);
+            assert_eq!(settings.location().city.as_deref(), Some("york"));
+            assert_eq!(settings.methods().prayer, PrayerMethod::ISNA);
+            assert_eq!(settings.imsak_minutes(), 20);
+        }
+
+        let settings = settings(&config, &["ummah", "--imsak-minutes", "5"]);
+        assert_eq!(settings.location().city.as_deref(), Some("leeds"));
+        assert_eq!(settings.imsak_minutes(), 5);
+    }
+
+    #[test]
+    fn sets_arguments_by_their_long_names() {
+        let mut config = Config::default();
+
+        config.set("--city", "leeds").unwrap();
+        config.set("sea-level", "true").unwrap();
+
+        assert_eq!(
+            config.iter().collect::<Vec<_>>(),
+            vec![("city", "leeds"), ("sea-level", "true")]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_arguments_and_invalid_values() {
+        let mut config = config(&[("city", "leeds")]);
+        let before = config.clone();
+
+        match config.set("colour", "green") {
+            Err(UmmahError::Config { key, reason }) => {
+                assert_eq!(key, "colour");
+                assert_eq!(reason, "no such argument");
+            }
+            result => panic!("Expected an unknown argument, got {:?}", result),
+        }
+        for (key, value) in [("sea-level", "yes"), ("prayer-method", "lunar")] {
+            assert!(
+                matches!(config.set(key, value), Err(UmmahError::Config { .. })),
+                "{} = {}",
+                key,
+                value
+            );
+        }
+        assert!(config.set("city", "leeeds").is_err());
+
+        assert_eq!(config, before);
+    }
+}
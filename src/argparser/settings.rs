@@ -1,43 +1,275 @@
-use crate::types::{AsrMethod, LatitudeMethod, PrayerMethod};
+#[cfg(feature = "cache")]
+use crate::core::cache::CacheKey;
+use crate::{
+    gazetteer,
+    source::{default_sources, Source, TimetableSource},
+    time::{hijri::HijriDate, zone},
+    types::{
+        AsrMethod, Coordinates, HijriCalendar, LatitudeMethod, MethodParameters, PrayerMethod,
+        PrayerName, UmmahError, UmmahResult,
+    },
+};
 
-use chrono::Datelike;
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
 use chrono_utilities::naive::DateTransitions;
 use serde::{Deserialize, Serialize};
 
-static LINK: &str = "https://www.salahtimes.com/";
+use std::ops::RangeInclusive;
 
-/// Settings for calculating prayer times and determining current month of prayers
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg(feature = "network")]
+static QUERY_DATE_FMT: &str = "%Y-%-m-%-d";
+
+/// Furthest distance to a place whose timetable is used for a location, in kilometres
+const NEAREST_PLACE_DISTANCE: f64 = 25.0;
+
+/// Number of places suggested for a misspelt city
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Settings for calculating prayer times
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PrayerSettings {
     methods: CalculationMethods,
     location: Location,
+    #[serde(default)]
+    offsets: PrayerOffsets,
+    #[serde(default = "default_imsak_minutes")]
+    imsak_minutes: i64,
+    /// Minutes after sunrise at which Duha begins
+    #[serde(default = "default_duha_minutes")]
+    duha_minutes: i64,
+    #[serde(default = "default_hijri_calendar")]
+    hijri_calendar: HijriCalendar,
+    /// Days added to the Hijri date, to follow a local moon sighting
+    #[serde(default)]
+    hijri_adjustment: i64,
+    /// Minutes before Fajr at which Suhoor ends, instead of at Imsak
+    #[serde(default)]
+    suhoor_margin: Option<i64>,
+    #[serde(default = "default_taraweeh_minutes")]
+    taraweeh_minutes: i64,
+    /// Download next month's times in the last days of the current one
+    #[serde(default)]
+    prefetch: bool,
+    /// Sources of the times, tried in order among those supporting the location and methods
+    #[serde(default = "default_sources")]
+    sources: Vec<Source>,
+    /// Proxy for downloads, instead of those in the environment
+    #[serde(default)]
+    proxy: Option<String>,
     is_audio_downloaded: bool,
-    current_month: u32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub(crate) struct CalculationMethods {
-    pub(super) latitude: LatitudeMethod,
-    pub(super) prayer: PrayerMethod,
-    pub(super) asr: AsrMethod,
+    pub(crate) latitude: LatitudeMethod,
+    pub(crate) prayer: PrayerMethod,
+    pub(crate) asr: AsrMethod,
+    #[serde(default)]
+    pub(crate) custom: Option<MethodParameters>,
+    #[serde(default = "default_nearest_latitude")]
+    pub(crate) nearest_latitude: f64,
+    #[serde(default)]
+    pub(crate) sea_level: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Location {
     pub(super) country: String,
-    pub(super) city: String,
+    /// City used by www.salahtimes.com, if the location has a page there
+    #[serde(default)]
+    pub(super) city: Option<String>,
+    #[serde(default)]
+    pub(crate) coordinates: Option<Coordinates>,
+    /// Height above sea level, in metres
+    #[serde(default)]
+    pub(crate) elevation: Option<f64>,
+    /// Atmospheric pressure, in millibars
+    #[serde(default)]
+    pub(crate) pressure: Option<f64>,
+    /// Air temperature, in degrees Celsius
+    #[serde(default)]
+    pub(crate) temperature: Option<f64>,
+    /// Angle of magnetic north east of true north, in degrees
+    #[serde(default)]
+    pub(crate) magnetic_declination: Option<f64>,
+    /// IANA time zone, such as Europe/London
+    #[serde(default)]
+    pub(crate) time_zone: Option<Tz>,
+}
+
+impl Location {
+    /// Fills in what the gazetteer knows about the location
+    ///
+    /// A city gives the coordinates and time zone, while coordinates give the
+    /// city of the nearest place on www.salahtimes.com, if one is close enough.
+    /// Places without a page on the website are left without a city.
+    /// Cities not in the gazetteer are kept only if coordinates are given with them.
+    /// Coordinates and time zones already set are kept.
+    ///
+    /// Returns [UmmahError::Location] if the coordinates are beyond the poles or the date line,
+    /// or [UmmahError::City] with suggestions if a city without coordinates is not in the gazetteer.
+    pub(crate) fn resolve(self) -> UmmahResult<Self> {
+        if let Some(coordinates) = self.coordinates {
+            if !(-90.0..=90.0).contains(&coordinates.latitude) {
+                return Err(UmmahError::Location {
+                    reason: format!(
+                        "latitude {} is not between -90 and 90",
+                        coordinates.latitude
+                    ),
+                });
+            }
+            if !(-180.0..=180.0).contains(&coordinates.longitude) {
+                return Err(UmmahError::Location {
+                    reason: format!(
+                        "longitude {} is not between -180 and 180",
+                        coordinates.longitude
+                    ),
+                });
+            }
+        }
+
+        let place = match (&self.city, self.coordinates) {
+            (Some(city), None) => {
+                Some(gazetteer::find(&self.country, city).ok_or_else(|| self.unknown_city(city))?)
+            }
+            (Some(city), Some(_)) => gazetteer::find(&self.country, city),
+            (None, Some(coordinates)) => gazetteer::nearest(coordinates).filter(|place| {
+                place.coordinates().distance_to(coordinates) <= NEAREST_PLACE_DISTANCE
+            }),
+            (None, None) => None,
+        };
+
+        let place = match place {
+            Some(place) => place,
+            None => return Ok(self),
+        };
+
+        Ok(Self {
+            country: place.country().to_owned(),
+            city: place.slug().map(ToOwned::to_owned),
+            coordinates: self.coordinates.or_else(|| Some(place.coordinates())),
+            time_zone: self.time_zone.or_else(|| Some(place.time_zone())),
+            ..self
+        })
+    }
+
+    /// Error for a city not in the gazetteer, suggesting the closest places in the country
+    fn unknown_city(&self, city: &str) -> UmmahError {
+        UmmahError::City {
+            city: city.to_owned(),
+            suggestions: gazetteer::search(city, Some(&self.country))
+                .iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|found| found.place().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Signed minutes added to each prayer, as published by many mosques
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PrayerOffsets {
+    pub(crate) fajr: i64,
+    pub(crate) dhuhr: i64,
+    pub(crate) asr: i64,
+    pub(crate) maghrib: i64,
+    pub(crate) isha: i64,
+}
+
+impl PrayerOffsets {
+    /// Gets the offset for a prayer, in minutes
+    pub(crate) fn get(&self, name: PrayerName) -> i64 {
+        match name {
+            PrayerName::Fajr => self.fajr,
+            PrayerName::Dhuhr => self.dhuhr,
+            PrayerName::Asr => self.asr,
+            PrayerName::Maghrib => self.maghrib,
+            PrayerName::Isha => self.isha,
+        }
+    }
+}
+
+/// Minutes before Fajr for Imsak in settings cached without one
+fn default_imsak_minutes() -> i64 {
+    10
+}
+
+/// Minutes after sunrise for Duha in settings cached without one
+fn default_duha_minutes() -> i64 {
+    15
+}
+
+/// Minutes after Isha for Taraweeh in settings cached without one
+fn default_taraweeh_minutes() -> i64 {
+    15
+}
+
+/// Calendar for Hijri dates in settings cached without one
+fn default_hijri_calendar() -> HijriCalendar {
+    HijriCalendar::UmmAlQura
+}
+
+/// Latitude used by [LatitudeMethod::NearestLatitude] for settings cached without one
+fn default_nearest_latitude() -> f64 {
+    48.0
+}
+
+/// Dates of the whole month containing the given date, as requested from the website
+pub(crate) fn month_range(date: NaiveDate) -> RangeInclusive<NaiveDate> {
+    let start = NaiveDate::from_ymd(date.year(), date.month(), 1);
+    let end = NaiveDate::from_ymd(date.year(), date.month(), date.last_day_of_month());
+
+    start..=end
+}
+
+/// Splits a range of dates into its parts in each calendar month
+pub(crate) fn split_by_month(range: &RangeInclusive<NaiveDate>) -> Vec<RangeInclusive<NaiveDate>> {
+    let mut parts = Vec::new();
+    let mut start = *range.start();
+    while start <= *range.end() {
+        let end = *month_range(start).end().min(range.end());
+        parts.push(start..=end);
+        start = end.succ();
+    }
+
+    parts
+}
+
+impl CalculationMethods {
+    /// Angles and intervals for the selected prayer method
+    ///
+    /// The parameters of [PrayerMethod::Custom] are taken from the user's settings.
+    pub(crate) fn parameters(&self) -> UmmahResult<MethodParameters> {
+        match self.prayer {
+            PrayerMethod::Custom => self.custom,
+            prayer => prayer.parameters(),
+        }
+        .ok_or(UmmahError::Parameters)
+    }
 }
 
 impl PrayerSettings {
+    #[cfg(feature = "cli")]
     pub(crate) fn new(methods: CalculationMethods, location: Location) -> Self {
         Self {
             methods,
             location,
+            offsets: PrayerOffsets::default(),
+            imsak_minutes: default_imsak_minutes(),
+            duha_minutes: default_duha_minutes(),
+            hijri_calendar: default_hijri_calendar(),
+            hijri_adjustment: 0,
+            suhoor_margin: None,
+            taraweeh_minutes: default_taraweeh_minutes(),
+            prefetch: false,
+            sources: default_sources(),
+            proxy: None,
             is_audio_downloaded: false,
-            current_month: chrono::Local::now().month(),
         }
     }
 
+    #[cfg(feature = "cache")]
     pub(crate) fn with_audio_downloaded(self) -> Self {
         Self {
             is_audio_downloaded: true,
@@ -45,23 +277,293 @@ impl PrayerSettings {
         }
     }
 
-    /// Generates query out of settings
-    pub(crate) fn query(&self, current_month: chrono::NaiveDate) -> String {
-        let end_day = current_month.last_day_of_month();
-        let current_year = current_month.year();
-        let current_month = current_month.month();
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_imsak_minutes(self, imsak_minutes: i64) -> Self {
+        Self {
+            imsak_minutes,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_duha_minutes(self, duha_minutes: i64) -> Self {
+        Self {
+            duha_minutes,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_hijri_calendar(
+        self,
+        hijri_calendar: HijriCalendar,
+        adjustment: i64,
+    ) -> Self {
+        Self {
+            hijri_calendar,
+            hijri_adjustment: adjustment,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_ramadan(self, suhoor_margin: Option<i64>, taraweeh_minutes: i64) -> Self {
+        Self {
+            suhoor_margin,
+            taraweeh_minutes,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_prefetch(self, prefetch: bool) -> Self {
+        Self { prefetch, ..self }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_sources(self, sources: Vec<Source>) -> Self {
+        Self { sources, ..self }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_proxy(self, proxy: Option<String>) -> Self {
+        Self { proxy, ..self }
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn with_offsets(self, offsets: PrayerOffsets) -> Self {
+        Self { offsets, ..self }
+    }
+
+    /// Fills in the coordinates, time zone and city of the location from the gazetteer
+    ///
+    /// The command line does this itself, but settings deserialized from elsewhere,
+    /// such as a service's own configuration, may give only a city or coordinates.
+    pub fn resolve(self) -> UmmahResult<Self> {
+        Ok(Self {
+            location: self.location.resolve()?,
+            ..self
+        })
+    }
+
+    pub(crate) fn offsets(&self) -> &PrayerOffsets {
+        &self.offsets
+    }
+
+    pub(crate) fn imsak_minutes(&self) -> i64 {
+        self.imsak_minutes
+    }
+
+    pub(crate) fn duha_minutes(&self) -> i64 {
+        self.duha_minutes
+    }
+
+    pub(crate) fn suhoor_margin(&self) -> Option<i64> {
+        self.suhoor_margin
+    }
+
+    pub(crate) fn taraweeh_minutes(&self) -> i64 {
+        self.taraweeh_minutes
+    }
+
+    pub(crate) fn prefetch(&self) -> bool {
+        self.prefetch
+    }
+
+    #[cfg(feature = "network")]
+    pub(crate) fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+
+    /// Gets the time zone of the location, if it has one
+    pub(crate) fn time_zone(&self) -> Option<Tz> {
+        self.location.time_zone
+    }
+
+    /// Gets the current date at the location
+    pub fn today(&self) -> NaiveDate {
+        zone::today(self.location.time_zone)
+    }
+
+    /// Converts a date using the user's Hijri calendar and adjustment
+    pub(crate) fn hijri_date(&self, date: NaiveDate) -> HijriDate {
+        HijriDate::from_gregorian(date, self.hijri_calendar, self.hijri_adjustment)
+    }
+
+    pub(crate) fn methods(&self) -> &CalculationMethods {
+        &self.methods
+    }
+
+    pub(crate) fn location(&self) -> &Location {
+        &self.location
+    }
+
+    /// Error for a city the gazetteer does not know, suggesting the closest places
+    ///
+    /// Returns [None] if there is no city or the gazetteer knows it.
+    #[cfg(feature = "network")]
+    pub(crate) fn unknown_city(&self) -> Option<UmmahError> {
+        let city = self.location.city.as_deref()?;
+        gazetteer::find(&self.location.country, city)
+            .is_none()
+            .then(|| self.location.unknown_city(city))
+    }
+
+    /// Checks if www.salahtimes.com supports the selected prayer method and location
+    #[cfg(feature = "network")]
+    pub(crate) fn is_downloadable(&self) -> bool {
+        self.location.city.is_some()
+            && self.methods.prayer.salahtimes_id().is_some()
+            && self.methods.latitude.salahtimes_id().is_some()
+    }
+
+    /// Identifies the downloaded timetable of the month containing the date
+    #[cfg(feature = "cache")]
+    pub(crate) fn cache_key(&self, date: NaiveDate) -> CacheKey {
+        CacheKey {
+            country: self.location.country.clone(),
+            city: self.location.city.clone().unwrap_or_default(),
+            coordinates: self.location.coordinates,
+            latitude: self.methods.latitude,
+            prayer: self.methods.prayer,
+            asr: self.methods.asr,
+            time_zone: self.location.time_zone,
+            year: date.year(),
+            month: date.month(),
+        }
+    }
+
+    /// Gets the sources that support the location and methods, in the order they are tried
+    ///
+    /// Fails with [UmmahError::City] if none do because the city is not in the gazetteer,
+    /// so has no coordinates, or [UmmahError::Source] otherwise.
+    pub(crate) fn sources(&self) -> UmmahResult<Vec<Box<dyn TimetableSource>>> {
+        let sources = self
+            .sources
+            .iter()
+            .map(Source::build)
+            .filter(|source| source.supports(self))
+            .collect::<Vec<_>>();
+
+        if sources.is_empty() {
+            return Err(match (&self.location.city, self.location.coordinates) {
+                (Some(city), None) => self.location.unknown_city(city),
+                _ => UmmahError::Source,
+            });
+        }
 
-        let start_date = format!("{}-{}-01", current_year, current_month);
-        let end_date = format!("{}-{}-{}", current_year, current_month, end_day);
+        Ok(sources)
+    }
 
+    /// Generates query for www.salahtimes.com, or a server at `link` answering in the same way
+    #[cfg(feature = "network")]
+    pub(crate) fn query(&self, link: &str, range: &RangeInclusive<NaiveDate>) -> String {
         format!(
             "{}/{}/{}/csv?highlatitudemethod={}&prayercalculationmethod={}&asarcalculationmethod={}&start={}&end={}",
-            LINK, self.location.country, self.location.city,
-            self.methods.latitude as u8,
-            self.methods.prayer as u8,
+            link, self.location.country, self.location.city.as_deref().unwrap_or_default(),
+            self.methods.latitude.salahtimes_id().unwrap_or_default(),
+            self.methods.prayer.salahtimes_id().unwrap_or_default(),
             self.methods.asr as u8,
-            start_date,
-            end_date
+            range.start().format(QUERY_DATE_FMT),
+            range.end().format(QUERY_DATE_FMT)
         )
     }
 }
+
+/// Settings for tests, at the coordinates and with the methods written as in a settings file
+#[cfg(test)]
+pub(crate) fn test_settings(
+    coordinates: Coordinates,
+    time_zone: &str,
+    methods: &str,
+) -> PrayerSettings {
+    serde_yaml::from_str(&format!(
+        "methods: {}\nlocation: {{country: uk, coordinates: {{latitude: {}, longitude: {}}}, time_zone: {}}}\nis_audio_downloaded: false",
+        methods, coordinates.latitude, coordinates.longitude, time_zone
+    ))
+    .expect("Reading test settings")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::TimingName;
+
+    fn location(city: &str, coordinates: Option<Coordinates>) -> Location {
+        Location {
+            country: "uk".to_owned(),
+            city: Some(city.to_owned()),
+            coordinates,
+            elevation: None,
+            pressure: None,
+            temperature: None,
+            magnetic_declination: None,
+            time_zone: None,
+        }
+    }
+
+    #[test]
+    fn suggests_places_for_misspelt_cities() {
+        match location("leeeds", None).resolve() {
+            Err(UmmahError::City { city, suggestions }) => {
+                assert_eq!(city, "leeeds");
+                assert!(
+                    suggestions.contains(&"Leeds, UK".to_owned()),
+                    "{:?}",
+                    suggestions
+                );
+            }
+            result => panic!("Expected an unknown city, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_cities_with_coordinates() {
+        let coordinates = Coordinates {
+            latitude: 53.8,
+            longitude: -1.55,
+        };
+
+        let location = location("leeeds", Some(coordinates)).resolve().unwrap();
+
+        assert_eq!(location.city.as_deref(), Some("leeeds"));
+        assert_eq!(location.coordinates, Some(coordinates));
+    }
+
+    #[test]
+    fn fills_in_known_cities() {
+        let location = location("St Albans", None).resolve().unwrap();
+
+        assert_eq!(location.city.as_deref(), Some("st-albans"));
+        assert!(location.coordinates.is_some());
+        assert!(location.time_zone.is_some());
+    }
+
+    #[test]
+    fn starts_duha_the_set_minutes_after_sunrise() {
+        let date = NaiveDate::from_ymd(2026, 6, 21);
+        let settings = test_settings(
+            Coordinates {
+                latitude: 51.5074,
+                longitude: -0.1278,
+            },
+            "Europe/London",
+            "{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}",
+        );
+        assert_eq!(settings.duha_minutes(), 15);
+
+        let settings = PrayerSettings {
+            duha_minutes: 25,
+            ..settings
+        };
+        let timetable = crate::calculator::calculate_timetable(&settings, &(date..=date)).unwrap();
+        let day = timetable.select_by_date(date).unwrap();
+        let time = |name| day.get_timing(name).unwrap().get_datetime();
+
+        assert_eq!(
+            time(TimingName::Duha) - time(TimingName::Sunrise),
+            chrono::Duration::minutes(25)
+        );
+    }
+}
@@ -0,0 +1,896 @@
+//! Local astronomical calculation of prayer times
+//!
+//! Produces the same [Month] and [Day] structures as the downloaded
+//! timetable, so the times can be obtained without network access.
+
+pub(crate) mod solar;
+
+use self::solar::{
+    acos, acot, cos, fix_hour, julian_date, rise_set_angle, sin, solar_position, tan,
+};
+
+use crate::{
+    argparser::settings::{month_range, CalculationMethods, PrayerSettings},
+    core::{get_performed_status, prayer::Prayer, timing::Timing},
+    time::{day::Day, hijri::HijriDate, month::Month, timetable::Timetable, zone},
+    types::{
+        Coordinates, LatitudeAdjustment, LatitudeMethod, MethodParameters, PrayerName, TimingName,
+        Twilight, UmmahError, UmmahResult,
+    },
+};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate};
+use chrono_tz::Tz;
+
+use std::ops::RangeInclusive;
+
+/// Angle of the sun below the horizon at sunrise and sunset, as used by the website
+const SEA_LEVEL_RISE_SET_ANGLE: f64 = 0.833;
+
+/// Number of times the calculation is refined using the previous estimate
+const ITERATIONS: usize = 2;
+
+/// Number of days searched by [LatitudeMethod::NearestDay] for a day with twilight
+const NEAREST_DAY_LIMIT: i64 = 183;
+
+/// Rule used on days where the chosen latitude method gives no time
+const FALLBACK_LATITUDE_METHOD: LatitudeMethod = LatitudeMethod::OneSeventh;
+
+/// Latitude below which the Moonsighting Committee's seasonal limits replace any latitude rule
+const SEASONAL_LATITUDE_LIMIT: f64 = 55.0;
+
+/// Calculates the prayer times for every day of the month containing `date`
+pub fn calculate_month(settings: &PrayerSettings, date: NaiveDate) -> UmmahResult<Month> {
+    calculate_timetable(settings, &month_range(date))
+}
+
+/// Calculates the prayer times for every day in the range
+pub fn calculate_timetable(
+    settings: &PrayerSettings,
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    let mut timetable = calculate_days(settings, range)?;
+    timetable.apply_settings(settings);
+
+    Ok(timetable)
+}
+
+/// Calculates the prayer times for every day in the range, before the user's offsets are applied
+pub(crate) fn calculate_days(
+    settings: &PrayerSettings,
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    let calculator = PrayerCalculator::new(settings)?;
+
+    let days = (0..=(*range.end() - *range.start()).num_days())
+        .map(|days| *range.start() + Duration::days(days))
+        .map(|date| calculator.day(date, settings.hijri_date(date)))
+        .collect::<UmmahResult<Vec<_>>>()?;
+
+    Ok(Timetable::new(days))
+}
+
+/// Calculates the prayer times for a single day
+pub fn calculate_day(settings: &PrayerSettings, date: NaiveDate) -> UmmahResult<Day> {
+    let calculator = PrayerCalculator::new(settings)?;
+
+    // The following morning is needed to find the middle of the night
+    let tomorrow = date + Duration::days(1);
+    let mut days = Month::new(vec![
+        calculator.day(date, settings.hijri_date(date))?,
+        calculator.day(tomorrow, settings.hijri_date(tomorrow))?,
+    ]);
+    days.apply_settings(settings);
+
+    days.select_by_date(date)
+        .cloned()
+        .ok_or(UmmahError::Prayer { date })
+}
+
+/// Times of the day in hours, measured in local solar time
+#[derive(Debug, Clone, Copy)]
+struct SolarTimes {
+    fajr: f64,
+    sunrise: f64,
+    dhuhr: f64,
+    asr: f64,
+    sunset: f64,
+    maghrib: f64,
+    isha: f64,
+}
+
+impl SolarTimes {
+    fn get(&self, name: PrayerName) -> f64 {
+        match name {
+            PrayerName::Fajr => self.fajr,
+            PrayerName::Dhuhr => self.dhuhr,
+            PrayerName::Asr => self.asr,
+            PrayerName::Maghrib => self.maghrib,
+            PrayerName::Isha => self.isha,
+        }
+    }
+
+    fn get_mut(&mut self, name: PrayerName) -> &mut f64 {
+        match name {
+            PrayerName::Fajr => &mut self.fajr,
+            PrayerName::Dhuhr => &mut self.dhuhr,
+            PrayerName::Asr => &mut self.asr,
+            PrayerName::Maghrib => &mut self.maghrib,
+            PrayerName::Isha => &mut self.isha,
+        }
+    }
+}
+
+impl Default for SolarTimes {
+    fn default() -> Self {
+        Self {
+            fajr: 5.0,
+            sunrise: 6.0,
+            dhuhr: 12.0,
+            asr: 13.0,
+            sunset: 18.0,
+            maghrib: 18.0,
+            isha: 18.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PrayerCalculator {
+    coordinates: Coordinates,
+    methods: CalculationMethods,
+    parameters: MethodParameters,
+    rise_set_angle: f64,
+    /// Zone of the times, or the system's zone if [None]
+    time_zone: Option<Tz>,
+}
+
+impl PrayerCalculator {
+    fn new(settings: &PrayerSettings) -> UmmahResult<Self> {
+        let location = settings.location();
+        let coordinates = location.coordinates.ok_or(UmmahError::Coordinates)?;
+
+        let rise_set_angle = if settings.methods().sea_level {
+            SEA_LEVEL_RISE_SET_ANGLE
+        } else {
+            rise_set_angle(location.elevation, location.pressure, location.temperature)
+        };
+
+        Ok(Self {
+            coordinates,
+            methods: *settings.methods(),
+            parameters: settings.methods().parameters()?,
+            rise_set_angle,
+            time_zone: location.time_zone,
+        })
+    }
+
+    fn day(&self, date: NaiveDate, hijri: HijriDate) -> UmmahResult<Day> {
+        let (times, adjustment) = self.for_hijri_date(hijri).solar_times(date);
+
+        let prayers = [
+            (PrayerName::Fajr, times.fajr),
+            (
+                PrayerName::Dhuhr,
+                times.dhuhr + self.parameters.dhuhr as f64 / 60.0,
+            ),
+            (PrayerName::Asr, times.asr),
+            (PrayerName::Maghrib, times.maghrib),
+            (PrayerName::Isha, times.isha),
+        ]
+        .map(|(name, hours)| {
+            self.local_time(date, hours)
+                .map(|time| Prayer::new(name, time, get_performed_status(time)))
+        });
+
+        let [fajr, dhuhr, asr, maghrib, isha] = prayers;
+
+        let timings = vec![
+            Timing::new(TimingName::Sunrise, self.local_time(date, times.sunrise)?),
+            Timing::new(TimingName::Sunset, self.local_time(date, times.sunset)?),
+        ];
+
+        Ok(Day::new(date, [fajr?, dhuhr?, asr?, maghrib?, isha?])
+            .with_timings(timings)
+            .with_adjustment(adjustment))
+    }
+
+    /// Uses the method's Isha for Ramadan, if it has one and the date falls in Ramadan
+    fn for_hijri_date(&self, hijri: HijriDate) -> Self {
+        match self.parameters.isha_in_ramadan {
+            Some(isha) if hijri.is_ramadan() => Self {
+                parameters: MethodParameters {
+                    isha,
+                    ..self.parameters
+                },
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+
+    fn solar_times(&self, date: NaiveDate) -> (SolarTimes, Option<LatitudeAdjustment>) {
+        let times = self.unadjusted_times(date);
+        if !times.sunrise.is_finite() || !times.sunset.is_finite() {
+            return self
+                .nearest_latitude_day(date, times)
+                .unwrap_or((times, None));
+        }
+
+        self.adjust_high_latitudes(date, times, self.methods.latitude)
+            .or_else(|| self.adjust_high_latitudes(date, times, FALLBACK_LATITUDE_METHOD))
+            .unwrap_or((times, None))
+    }
+
+    /// Every time of the day at the nearest latitude, for days when the sun does not rise or set
+    ///
+    /// Only the prayers whose time differs from the location's own are recorded as adjusted.
+    /// Returns [None] if the location is not beyond the nearest latitude.
+    fn nearest_latitude_day(
+        &self,
+        date: NaiveDate,
+        own_times: SolarTimes,
+    ) -> Option<(SolarTimes, Option<LatitudeAdjustment>)> {
+        let calculator = self.at_nearest_latitude()?;
+        let (times, _) = calculator.solar_times(date);
+
+        let prayers = [
+            PrayerName::Fajr,
+            PrayerName::Dhuhr,
+            PrayerName::Asr,
+            PrayerName::Maghrib,
+            PrayerName::Isha,
+        ]
+        .into_iter()
+        .filter(|&name| !is_same_minute(times.get(name), own_times.get(name)))
+        .collect::<Vec<_>>();
+
+        let adjustment = (!prayers.is_empty()).then(|| LatitudeAdjustment {
+            rule: LatitudeMethod::NearestLatitude,
+            prayers,
+        });
+
+        Some((times, adjustment))
+    }
+
+    /// Computes the times before any high latitude rule is applied
+    fn unadjusted_times(&self, date: NaiveDate) -> SolarTimes {
+        let julian_date = julian_date(date) - self.coordinates.longitude / 360.0;
+
+        let mut times = SolarTimes::default();
+        for _ in 0..ITERATIONS {
+            times = self.refine(julian_date, times);
+        }
+
+        self.adjust_seasons(date, times)
+    }
+
+    /// Computes every time using the sun's position at the previous estimate
+    fn refine(&self, julian_date: f64, estimate: SolarTimes) -> SolarTimes {
+        let sunrise = self.sun_angle_time(julian_date, self.rise_set_angle, estimate.sunrise, true);
+        let sunset = self.sun_angle_time(julian_date, self.rise_set_angle, estimate.sunset, false);
+
+        let fajr = match self.parameters.fajr {
+            Twilight::Angle(angle) | Twilight::Seasonal(angle) => {
+                self.sun_angle_time(julian_date, angle, estimate.fajr, true)
+            }
+            Twilight::Minutes(minutes) => sunrise - minutes as f64 / 60.0,
+        };
+
+        let maghrib = match self.parameters.maghrib {
+            Twilight::Angle(angle) | Twilight::Seasonal(angle) => {
+                self.sun_angle_time(julian_date, angle, estimate.maghrib, false)
+            }
+            Twilight::Minutes(minutes) => sunset + minutes as f64 / 60.0,
+        };
+
+        let isha = match self.parameters.isha {
+            Twilight::Angle(angle) | Twilight::Seasonal(angle) => {
+                self.sun_angle_time(julian_date, angle, estimate.isha, false)
+            }
+            Twilight::Minutes(minutes) => maghrib + minutes as f64 / 60.0,
+        };
+
+        SolarTimes {
+            fajr,
+            sunrise,
+            dhuhr: mid_day(julian_date, estimate.dhuhr),
+            asr: self.asr_time(julian_date, estimate.asr),
+            sunset,
+            maghrib,
+            isha,
+        }
+    }
+
+    /// Time at which the sun reaches `angle` degrees below the horizon
+    ///
+    /// Returns NaN if the sun never reaches that angle on the given day.
+    fn sun_angle_time(
+        &self,
+        julian_date: f64,
+        angle: f64,
+        estimate: f64,
+        before_noon: bool,
+    ) -> f64 {
+        let latitude = self.coordinates.latitude;
+        let declination = solar_position(julian_date + estimate / 24.0).declination;
+        let noon = mid_day(julian_date, estimate);
+
+        let hour_angle = acos(
+            (-sin(angle) - sin(declination) * sin(latitude)) / (cos(declination) * cos(latitude)),
+        ) / 15.0;
+
+        if before_noon {
+            noon - hour_angle
+        } else {
+            noon + hour_angle
+        }
+    }
+
+    /// Time at which an object's shadow reaches the length required by the Asr method
+    fn asr_time(&self, julian_date: f64, estimate: f64) -> f64 {
+        let declination = solar_position(julian_date + estimate / 24.0).declination;
+        let shadow_length = self.methods.asr.shadow_length();
+
+        let angle = -acot(shadow_length + tan((self.coordinates.latitude - declination).abs()));
+
+        self.sun_angle_time(julian_date, angle, estimate, false)
+    }
+
+    /// Limits Fajr and Isha to the Moonsighting Committee's seasonal intervals
+    fn adjust_seasons(&self, date: NaiveDate, times: SolarTimes) -> SolarTimes {
+        let latitude = self.coordinates.latitude;
+        let days = days_since_solstice(date, latitude);
+
+        let fajr = match self.parameters.fajr {
+            Twilight::Seasonal(_) => {
+                let limit = times.sunrise - seasonal_fajr_minutes(latitude, days) / 60.0;
+                if times.fajr.is_nan() || times.fajr < limit {
+                    limit
+                } else {
+                    times.fajr
+                }
+            }
+            _ => times.fajr,
+        };
+
+        let isha = match self.parameters.isha {
+            Twilight::Seasonal(_) => {
+                let limit = times.sunset + seasonal_isha_minutes(latitude, days) / 60.0;
+                if times.isha.is_nan() || times.isha > limit {
+                    limit
+                } else {
+                    times.isha
+                }
+            }
+            _ => times.isha,
+        };
+
+        SolarTimes {
+            fajr,
+            isha,
+            ..times
+        }
+    }
+
+    /// Moves the times set by an angle as required by the latitude method
+    ///
+    /// Returns [None] if the method cannot give a time for every prayer.
+    fn adjust_high_latitudes(
+        &self,
+        date: NaiveDate,
+        times: SolarTimes,
+        method: LatitudeMethod,
+    ) -> Option<(SolarTimes, Option<LatitudeAdjustment>)> {
+        let mut adjusted_times = times;
+        let mut adjustment: Option<LatitudeAdjustment> = None;
+
+        for name in [PrayerName::Fajr, PrayerName::Maghrib, PrayerName::Isha] {
+            if let Some((rule, time)) = self.adjusted_time(date, name, &times, method) {
+                *adjusted_times.get_mut(name) = time;
+                adjustment
+                    .get_or_insert(LatitudeAdjustment {
+                        rule,
+                        prayers: Vec::new(),
+                    })
+                    .prayers
+                    .push(name);
+            }
+        }
+
+        let prayers = [PrayerName::Fajr, PrayerName::Maghrib, PrayerName::Isha];
+        if prayers
+            .iter()
+            .all(|&name| adjusted_times.get(name).is_finite())
+        {
+            Some((adjusted_times, adjustment))
+        } else {
+            None
+        }
+    }
+
+    /// Finds the replacement for a time set by an angle, along with the rule used
+    ///
+    /// Returns [None] if the time does not need to be moved, or the rule cannot move it.
+    fn adjusted_time(
+        &self,
+        date: NaiveDate,
+        name: PrayerName,
+        times: &SolarTimes,
+        method: LatitudeMethod,
+    ) -> Option<(LatitudeMethod, f64)> {
+        let (time, base, twilight, before_sunrise) = match name {
+            PrayerName::Fajr => (times.fajr, times.sunrise, self.parameters.fajr, true),
+            PrayerName::Maghrib => (times.maghrib, times.sunset, self.parameters.maghrib, false),
+            PrayerName::Isha => (times.isha, times.sunset, self.parameters.isha, false),
+            _ => return None,
+        };
+
+        let angle = match twilight {
+            Twilight::Seasonal(_) if self.coordinates.latitude.abs() < SEASONAL_LATITUDE_LIMIT => {
+                return None
+            }
+            Twilight::Angle(angle) | Twilight::Seasonal(angle) => angle,
+            Twilight::Minutes(_) => return None,
+        };
+
+        let rule = match method {
+            LatitudeMethod::Auto if time.is_finite() => return None,
+            LatitudeMethod::Auto => LatitudeMethod::for_latitude(self.coordinates.latitude),
+            rule => rule,
+        };
+
+        let adjusted = match rule.night_portion(angle) {
+            Some(portion) => {
+                let portion = portion * fix_hour(times.sunrise - times.sunset);
+                let interval = if before_sunrise {
+                    fix_hour(base - time)
+                } else {
+                    fix_hour(time - base)
+                };
+
+                if time.is_finite() && interval <= portion {
+                    return None;
+                }
+
+                if before_sunrise {
+                    base - portion
+                } else {
+                    base + portion
+                }
+            }
+            None if time.is_finite() => return None,
+            None if rule == LatitudeMethod::NearestLatitude => {
+                self.nearest_latitude_time(date, name)?
+            }
+            None => self.nearest_day_time(date, name)?,
+        };
+
+        Some((rule, adjusted))
+    }
+
+    /// Time of the prayer at the nearest latitude set in the calculation methods
+    fn nearest_latitude_time(&self, date: NaiveDate, name: PrayerName) -> Option<f64> {
+        let time = self.at_nearest_latitude()?.unadjusted_times(date).get(name);
+        time.is_finite().then(|| time)
+    }
+
+    /// The same calculation at the nearest latitude set in the calculation methods
+    ///
+    /// Returns [None] if the location is not beyond that latitude.
+    fn at_nearest_latitude(&self) -> Option<Self> {
+        let nearest_latitude = self.methods.nearest_latitude;
+        if self.coordinates.latitude.abs() <= nearest_latitude {
+            return None;
+        }
+
+        Some(PrayerCalculator {
+            coordinates: Coordinates {
+                latitude: nearest_latitude.copysign(self.coordinates.latitude),
+                ..self.coordinates
+            },
+            ..*self
+        })
+    }
+
+    /// Time of the prayer on the last day it occurred
+    fn nearest_day_time(&self, date: NaiveDate, name: PrayerName) -> Option<f64> {
+        (1..=NEAREST_DAY_LIMIT)
+            .map(|days| {
+                *self
+                    .unadjusted_times(date - Duration::days(days))
+                    .get_mut(name)
+            })
+            .find(|time| time.is_finite())
+    }
+
+    /// Converts local solar hours into the wall clock time of this machine
+    fn local_time(&self, date: NaiveDate, hours: f64) -> UmmahResult<DateTime<FixedOffset>> {
+        if !hours.is_finite() {
+            return Err(UmmahError::Prayer { date });
+        }
+
+        let utc_hours = hours - self.coordinates.longitude / 15.0;
+        let minutes = (utc_hours * 60.0).round() as i64;
+
+        let utc = date.and_hms(0, 0, 0) + Duration::minutes(minutes);
+
+        Ok(zone::from_utc(self.time_zone, utc))
+    }
+}
+
+/// Checks if two times in hours round to the same minute, which they cannot if either does not occur
+fn is_same_minute(lhs: f64, rhs: f64) -> bool {
+    (lhs * 60.0).round() == (rhs * 60.0).round()
+}
+
+/// Time at which the sun crosses the meridian
+fn mid_day(julian_date: f64, estimate: f64) -> f64 {
+    let equation_of_time = solar_position(julian_date + estimate / 24.0).equation_of_time;
+    fix_hour(12.0 - equation_of_time)
+}
+
+/// Number of days since the winter solstice of the location's hemisphere
+fn days_since_solstice(date: NaiveDate, latitude: f64) -> f64 {
+    let days_in_year = if NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some() {
+        366
+    } else {
+        365
+    };
+
+    let days = if latitude >= 0.0 {
+        date.ordinal() as i32 + 10
+    } else {
+        date.ordinal() as i32 - (days_in_year - 193)
+    };
+
+    f64::from(days.rem_euclid(days_in_year))
+}
+
+/// Minutes before sunrise at which Fajr begins at the latest
+fn seasonal_fajr_minutes(latitude: f64, days: f64) -> f64 {
+    let latitude = latitude.abs() / 55.0;
+    seasonal_interval(
+        days,
+        [
+            75.0 + 28.65 * latitude,
+            75.0 + 19.44 * latitude,
+            75.0 + 32.74 * latitude,
+            75.0 + 48.10 * latitude,
+        ],
+    )
+}
+
+/// Minutes after sunset at which Isha begins at the latest
+fn seasonal_isha_minutes(latitude: f64, days: f64) -> f64 {
+    let latitude = latitude.abs() / 55.0;
+    seasonal_interval(
+        days,
+        [
+            75.0 + 25.60 * latitude,
+            75.0 + 2.050 * latitude,
+            75.0 - 9.210 * latitude,
+            75.0 + 6.140 * latitude,
+        ],
+    )
+}
+
+/// Interpolates between the intervals at the winter solstice,
+/// the equinoxes and the summer solstice
+fn seasonal_interval(days: f64, [a, b, c, d]: [f64; 4]) -> f64 {
+    if days < 91.0 {
+        a + (b - a) / 91.0 * days
+    } else if days < 137.0 {
+        b + (c - b) / 46.0 * (days - 91.0)
+    } else if days < 183.0 {
+        c + (d - c) / 46.0 * (days - 137.0)
+    } else if days < 229.0 {
+        d + (c - d) / 46.0 * (days - 183.0)
+    } else if days < 275.0 {
+        c + (b - c) / 46.0 * (days - 229.0)
+    } else {
+        b + (a - b) / 91.0 * (days - 275.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::argparser::settings::test_settings;
+
+    use chrono::NaiveTime;
+
+    const LONDON: Coordinates = Coordinates {
+        latitude: 51.5074,
+        longitude: -0.1278,
+    };
+
+    fn london(methods: &str) -> PrayerSettings {
+        test_settings(LONDON, "Europe/London", methods)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    /// Asserts the prayer is within a minute of the wall clock time
+    fn assert_time(day: &Day, name: PrayerName, expected: &str) {
+        let expected = NaiveTime::parse_from_str(expected, "%H:%M").unwrap();
+        let time = day.get_prayer(name).get_datetime().time();
+        let difference = (time - expected).num_minutes().abs();
+        assert!(
+            difference <= 1,
+            "{:?} on {} is {}, expected {}",
+            name,
+            day.get_date(),
+            time,
+            expected
+        );
+    }
+
+    /// Asserts the timing is within a minute of the wall clock time
+    fn assert_timing(day: &Day, name: TimingName, expected: &str) {
+        let expected = NaiveTime::parse_from_str(expected, "%H:%M").unwrap();
+        let time = day.get_timing(name).unwrap().get_datetime().time();
+        let difference = (time - expected).num_minutes().abs();
+        assert!(
+            difference <= 1,
+            "{:?} on {} is {}, expected {}",
+            name,
+            day.get_date(),
+            time,
+            expected
+        );
+    }
+
+    #[test]
+    fn calculates_a_summer_day_in_london() {
+        let settings = london("{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}");
+
+        let day = calculate_day(&settings, date(2026, 6, 21)).unwrap();
+
+        assert_timing(&day, TimingName::Sunrise, "04:43");
+        assert_time(&day, PrayerName::Dhuhr, "13:02");
+        assert_time(&day, PrayerName::Asr, "17:25");
+        assert_timing(&day, TimingName::Sunset, "21:21");
+        assert_time(&day, PrayerName::Maghrib, "21:21");
+        // A seventh of the 7h 21m night before sunrise and after sunset
+        assert_time(&day, PrayerName::Fajr, "03:40");
+        assert_time(&day, PrayerName::Isha, "22:25");
+        assert_eq!(
+            day.get_adjustment(),
+            Some(&LatitudeAdjustment {
+                rule: LatitudeMethod::OneSeventh,
+                prayers: vec![PrayerName::Fajr, PrayerName::Isha],
+            })
+        );
+    }
+
+    #[test]
+    fn calculates_an_equinox_in_london() {
+        let settings = london("{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}");
+
+        let day = calculate_day(&settings, date(2026, 3, 20)).unwrap();
+
+        assert_time(&day, PrayerName::Fajr, "04:22");
+        assert_timing(&day, TimingName::Sunrise, "06:03");
+        assert_time(&day, PrayerName::Dhuhr, "12:08");
+        assert_timing(&day, TimingName::Sunset, "18:14");
+        assert_time(&day, PrayerName::Isha, "19:55");
+    }
+
+    const ABERDEEN: Coordinates = Coordinates {
+        latitude: 57.1497,
+        longitude: -2.0943,
+    };
+
+    const TROMSO: Coordinates = Coordinates {
+        latitude: 69.6492,
+        longitude: 18.9553,
+    };
+
+    fn mwl(latitude: &str) -> String {
+        format!(
+            "{{latitude: {}, prayer: MWL, asr: Shafi, sea_level: true}}",
+            latitude
+        )
+    }
+
+    /// The day at midsummer in Aberdeen, where the sun never reaches the MWL angles
+    fn aberdeen_midsummer(latitude: &str) -> Day {
+        let settings = test_settings(ABERDEEN, "Europe/London", &mwl(latitude));
+        calculate_day(&settings, date(2026, 6, 21)).unwrap()
+    }
+
+    fn prayer_time(day: &Day, name: PrayerName) -> DateTime<FixedOffset> {
+        day.get_prayer(name).get_datetime()
+    }
+
+    fn timing_time(day: &Day, name: TimingName) -> DateTime<FixedOffset> {
+        day.get_timing(name).unwrap().get_datetime()
+    }
+
+    fn assert_minutes_apart(lhs: DateTime<FixedOffset>, rhs: DateTime<FixedOffset>) {
+        let difference = (lhs - rhs).num_minutes().abs();
+        assert!(difference <= 1, "{} is not {}", lhs, rhs);
+    }
+
+    fn adjustment(rule: LatitudeMethod, prayers: &[PrayerName]) -> Option<LatitudeAdjustment> {
+        Some(LatitudeAdjustment {
+            rule,
+            prayers: prayers.to_vec(),
+        })
+    }
+
+    /// Asserts Fajr and Isha are the portions of the night before sunrise and after sunset
+    fn assert_night_portions(day: &Day, fajr_portion: f64, isha_portion: f64) {
+        let sunrise = timing_time(day, TimingName::Sunrise);
+        let sunset = timing_time(day, TimingName::Sunset);
+        let night = (sunrise + Duration::days(1) - sunset).num_seconds() as f64;
+        let portion = |fraction: f64| Duration::seconds((night * fraction) as i64);
+
+        assert_minutes_apart(
+            prayer_time(day, PrayerName::Fajr),
+            sunrise - portion(fajr_portion),
+        );
+        assert_minutes_apart(
+            prayer_time(day, PrayerName::Isha),
+            sunset + portion(isha_portion),
+        );
+    }
+
+    #[test]
+    fn limits_twilight_to_a_seventh_of_the_night() {
+        let day = aberdeen_midsummer("OneSeventh");
+
+        assert_night_portions(&day, 1.0 / 7.0, 1.0 / 7.0);
+        assert_eq!(
+            day.get_adjustment().cloned(),
+            adjustment(
+                LatitudeMethod::OneSeventh,
+                &[PrayerName::Fajr, PrayerName::Isha]
+            )
+        );
+    }
+
+    #[test]
+    fn limits_twilight_by_the_angle() {
+        let day = aberdeen_midsummer("AngleBased");
+
+        assert_night_portions(&day, 18.0 / 60.0, 17.0 / 60.0);
+        assert_eq!(
+            day.get_adjustment().cloned(),
+            adjustment(
+                LatitudeMethod::AngleBased,
+                &[PrayerName::Fajr, PrayerName::Isha]
+            )
+        );
+    }
+
+    #[test]
+    fn limits_twilight_to_the_middle_of_the_night() {
+        let day = aberdeen_midsummer("MiddleOfNight");
+
+        assert_night_portions(&day, 0.5, 0.5);
+        // Isha falls at the moment Fajr begins the following morning
+        assert_minutes_apart(
+            prayer_time(&day, PrayerName::Fajr) + Duration::days(1),
+            prayer_time(&day, PrayerName::Isha),
+        );
+        assert_eq!(
+            day.get_adjustment().cloned(),
+            adjustment(
+                LatitudeMethod::MiddleOfNight,
+                &[PrayerName::Fajr, PrayerName::Isha]
+            )
+        );
+    }
+
+    #[test]
+    fn takes_twilight_from_the_nearest_latitude() {
+        let nearest = Coordinates {
+            latitude: 48.0,
+            ..ABERDEEN
+        };
+        let settings = test_settings(nearest, "Europe/London", &mwl("NearestLatitude"));
+        let at_nearest_latitude = calculate_day(&settings, date(2026, 6, 21)).unwrap();
+        assert_eq!(at_nearest_latitude.get_adjustment(), None);
+
+        for rule in ["NearestLatitude", "Auto"] {
+            let day = aberdeen_midsummer(rule);
+
+            for name in [PrayerName::Fajr, PrayerName::Isha] {
+                assert_minutes_apart(
+                    prayer_time(&day, name),
+                    prayer_time(&at_nearest_latitude, name),
+                );
+            }
+            assert_eq!(
+                day.get_adjustment().cloned(),
+                adjustment(
+                    LatitudeMethod::NearestLatitude,
+                    &[PrayerName::Fajr, PrayerName::Isha]
+                ),
+                "{}",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn takes_twilight_from_the_last_day_it_occurred() {
+        let settings = test_settings(ABERDEEN, "Europe/London", &mwl("NearestDay"));
+        let day = calculate_day(&settings, date(2026, 6, 21)).unwrap();
+
+        let last_fajr = (1..=NEAREST_DAY_LIMIT)
+            .map(|days| calculate_day(&settings, date(2026, 6, 21) - Duration::days(days)).unwrap())
+            .find(|day| !day.is_adjusted(PrayerName::Fajr))
+            .unwrap();
+        assert!(last_fajr.get_date() < date(2026, 6, 1));
+
+        assert_minutes_apart(
+            prayer_time(&day, PrayerName::Fajr),
+            prayer_time(&last_fajr, PrayerName::Fajr) + (day.get_date() - last_fajr.get_date()),
+        );
+        assert_eq!(
+            day.get_adjustment().cloned(),
+            adjustment(
+                LatitudeMethod::NearestDay,
+                &[PrayerName::Fajr, PrayerName::Isha]
+            )
+        );
+    }
+
+    #[test]
+    fn takes_polar_days_from_the_nearest_latitude() {
+        let nearest = Coordinates {
+            latitude: 48.0,
+            ..TROMSO
+        };
+
+        // The sun stays below the horizon in December and above it in June,
+        // so only Dhuhr is the same at Tromsø as at the nearest latitude
+        let moved = [
+            PrayerName::Fajr,
+            PrayerName::Asr,
+            PrayerName::Maghrib,
+            PrayerName::Isha,
+        ];
+
+        for month in [12, 6] {
+            let date = date(2026, month, 21);
+            let settings = test_settings(TROMSO, "Europe/Oslo", &mwl("OneSeventh"));
+            let day = calculate_day(&settings, date).unwrap();
+            let settings = test_settings(nearest, "Europe/Oslo", &mwl("OneSeventh"));
+            let at_nearest_latitude = calculate_day(&settings, date).unwrap();
+
+            for (prayer, expected) in day
+                .get_prayers()
+                .iter()
+                .zip(at_nearest_latitude.get_prayers().iter())
+            {
+                assert_minutes_apart(prayer.get_datetime(), expected.get_datetime());
+            }
+            assert_eq!(
+                day.get_adjustment().cloned(),
+                adjustment(LatitudeMethod::NearestLatitude, &moved),
+                "{}",
+                date
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_seasonal_limits_below_55_degrees() {
+        let settings = london(
+            "{latitude: OneSeventh, prayer: MoonsightingCommittee, asr: Shafi, sea_level: true}",
+        );
+
+        let day = calculate_day(&settings, date(2026, 6, 21)).unwrap();
+
+        assert_time(&day, PrayerName::Fajr, "02:43");
+        assert_time(&day, PrayerName::Isha, "22:43");
+        assert_eq!(day.get_adjustment(), None);
+    }
+}
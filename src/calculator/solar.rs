@@ -0,0 +1,185 @@
+//! Position of the sun for a given moment
+
+use chrono::{Datelike, NaiveDate};
+
+/// Julian date of the J2000.0 epoch
+const J2000: f64 = 2451545.0;
+
+/// Julian date offset from the days counted by [Datelike::num_days_from_ce]
+const CE_OFFSET: f64 = 1721424.5;
+
+/// Apparent radius of the sun, in degrees
+const SEMI_DIAMETER: f64 = 0.2667;
+
+/// Refraction at the horizon under standard conditions, in degrees
+const STANDARD_REFRACTION: f64 = 0.5667;
+
+/// Standard atmospheric pressure used by the refraction formula, in millibars
+const STANDARD_PRESSURE: f64 = 1010.0;
+
+/// Standard temperature used by the refraction formula, in degrees Celsius
+const STANDARD_TEMPERATURE: f64 = 10.0;
+
+/// Apparent position of the sun needed for prayer calculations
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SolarPosition {
+    /// Declination of the sun, in degrees
+    pub(crate) declination: f64,
+    /// Difference between apparent and mean solar time, in hours
+    pub(crate) equation_of_time: f64,
+}
+
+/// Julian date at midnight UTC of the given date
+pub(crate) fn julian_date(date: NaiveDate) -> f64 {
+    f64::from(date.num_days_from_ce()) + CE_OFFSET
+}
+
+/// Calculates the position of the sun at the given Julian date
+///
+/// Uses the low precision formulae from the U.S. Naval Observatory,
+/// accurate to about a minute of arc between 1950 and 2050.
+pub(crate) fn solar_position(julian_date: f64) -> SolarPosition {
+    let days = julian_date - J2000;
+
+    let mean_anomaly = fix_angle(357.529 + 0.98560028 * days);
+    let mean_longitude = fix_angle(280.459 + 0.98564736 * days);
+    let ecliptic_longitude =
+        fix_angle(mean_longitude + 1.915 * sin(mean_anomaly) + 0.020 * sin(2.0 * mean_anomaly));
+
+    let obliquity = 23.439 - 0.00000036 * days;
+
+    let right_ascension = atan2(
+        cos(obliquity) * sin(ecliptic_longitude),
+        cos(ecliptic_longitude),
+    ) / 15.0;
+
+    SolarPosition {
+        declination: asin(sin(obliquity) * sin(ecliptic_longitude)),
+        equation_of_time: mean_longitude / 15.0 - fix_hour(right_ascension),
+    }
+}
+
+/// Angle of the sun's centre below the horizon at sunrise and sunset
+///
+/// Allows for the solar disc, refraction through the atmosphere and
+/// the dip of the horizon seen from above sea level.
+pub(crate) fn rise_set_angle(
+    elevation: Option<f64>,
+    pressure: Option<f64>,
+    temperature: Option<f64>,
+) -> f64 {
+    let pressure = pressure.unwrap_or(STANDARD_PRESSURE);
+    let temperature = temperature.unwrap_or(STANDARD_TEMPERATURE);
+
+    let refraction =
+        STANDARD_REFRACTION * (pressure / STANDARD_PRESSURE) * (283.0 / (273.0 + temperature));
+    let dip = 0.0347 * elevation.unwrap_or_default().max(0.0).sqrt();
+
+    SEMI_DIAMETER + refraction + dip
+}
+
+pub(crate) fn sin(degrees: f64) -> f64 {
+    degrees.to_radians().sin()
+}
+
+pub(crate) fn cos(degrees: f64) -> f64 {
+    degrees.to_radians().cos()
+}
+
+pub(crate) fn tan(degrees: f64) -> f64 {
+    degrees.to_radians().tan()
+}
+
+pub(crate) fn asin(x: f64) -> f64 {
+    x.asin().to_degrees()
+}
+
+pub(crate) fn acos(x: f64) -> f64 {
+    x.acos().to_degrees()
+}
+
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x).to_degrees()
+}
+
+pub(crate) fn acot(x: f64) -> f64 {
+    (1.0 / x).atan().to_degrees()
+}
+
+/// Wraps an angle into the range `[0, 360)`
+pub(crate) fn fix_angle(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
+
+/// Wraps an hour into the range `[0, 24)`
+pub(crate) fn fix_hour(hours: f64) -> f64 {
+    hours.rem_euclid(24.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64, what: &str) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{} is {}, expected {}",
+            what,
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn counts_julian_dates_from_midnight() {
+        assert_eq!(julian_date(NaiveDate::from_ymd(2000, 1, 1)), J2000 - 0.5);
+        assert_eq!(julian_date(NaiveDate::from_ymd(1970, 1, 1)), 2440587.5);
+        assert_eq!(julian_date(NaiveDate::from_ymd(2026, 6, 21)), 2461212.5);
+    }
+
+    #[test]
+    fn finds_the_sun_through_the_year() {
+        // Noon UTC, with the declination in degrees and the equation of time in minutes
+        let cases = [
+            ((2000, 1, 1), -23.03, -3.3),
+            ((2026, 3, 20), 0.0, -7.5),
+            ((2026, 6, 21), 23.44, -1.8),
+            ((2026, 11, 3), -15.15, 16.4),
+            ((2026, 12, 21), -23.44, 1.9),
+        ];
+
+        for ((year, month, day), declination, equation_of_time) in cases {
+            let date = NaiveDate::from_ymd(year, month, day);
+            let position = solar_position(julian_date(date) + 0.5);
+
+            assert_close(
+                position.declination,
+                declination,
+                0.05,
+                &format!("Declination on {}", date),
+            );
+            assert_close(
+                position.equation_of_time * 60.0,
+                equation_of_time,
+                0.2,
+                &format!("Equation of time on {}", date),
+            );
+        }
+    }
+
+    #[test]
+    fn lowers_the_horizon_with_elevation() {
+        assert_close(
+            rise_set_angle(None, None, None),
+            0.8334,
+            0.001,
+            "Angle at sea level",
+        );
+        assert_close(
+            rise_set_angle(Some(100.0), None, None),
+            0.8334 + 0.347,
+            0.001,
+            "Angle at 100 metres",
+        );
+    }
+}
@@ -1,47 +1,85 @@
 //! Core module for obtaining and caching timetable
 //! and other relevant files
 
+#[cfg(feature = "network")]
+pub mod blocking;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "cache")]
 pub mod fs;
 pub mod prayer;
+pub mod qibla;
+#[cfg(feature = "network")]
 pub(crate) mod request_handler;
+#[cfg(feature = "html")]
 pub mod timetable_generator;
+pub mod timing;
 
-use self::{
-    fs::{get_cache_filepath, get_user_filepath, open_file, write_serialized_file},
-    request_handler::download_file,
-};
+#[cfg(feature = "cache")]
+use self::fs::{get_cache_filepath, get_user_filepath, open_file, write_serialized_file};
+use self::qibla::Qibla;
 
 use crate::{
-    argparser::settings::PrayerSettings,
-    request_parser::parse_csv_file,
-    time::{day::Day, month::Month},
-    types::UmmahResult,
+    argparser::settings::{month_range, split_by_month, PrayerSettings},
+    calculator::calculate_month,
+    source::{calculation::CalculationSource, TimetableSource},
+    time::{
+        day::Day,
+        month::Month,
+        ramadan::{self, Ramadan},
+        timetable::Timetable,
+    },
+    types::{Origin, Provenance, UmmahError, UmmahResult},
 };
 
-use chrono::{Datelike, Local};
+#[cfg(feature = "cache")]
+use chrono::Duration;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+#[cfg(feature = "cache")]
+use serde::{Deserialize, Serialize};
 
+use std::ops::RangeInclusive;
+#[cfg(feature = "cache")]
 use std::path::PathBuf;
 
+#[cfg(feature = "cache")]
 static CURRENT_MONTH: &str = "current_month.yaml";
+#[cfg(feature = "cache")]
 static CURRENT_SETTINGS: &str = ".current_settings.yaml";
 
-/// Collect all prayer times for the current month
+/// Days before the end of the month from which the next month is prefetched
+#[cfg(feature = "cache")]
+const PREFETCH_DAYS: i64 = 3;
+
+/// Where the cached current timetable came from and which dates it covers
+#[cfg(feature = "cache")]
+#[derive(Debug, Serialize, Deserialize)]
+struct CurrentSource {
+    settings: PrayerSettings,
+    provider: String,
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+/// Collect all prayer times for the current month, or the month containing the custom date
+///
+/// The times come from the first source in the settings that supports the
+/// location and methods, or the next if it fails, see [crate::source]. Each day
+/// records where its times came from in its [Provenance].
+///
+/// Downloading needs a tokio runtime, as reqwest does. Without one, use
+/// [blocking::get_prayer_times] instead.
 ///
 /// # Example
 /// ```
-/// use ummah::{
-///     core::{get_prayer_times, try_get_today},
-///     prayer::settings::PrayerSettings,
-///     types::{UmmahResult, LatitudeMethod, PrayerCalculationMethod, AsrCalculationMethod}
-/// };
-///
-/// async fn test() -> UmmahResult<()> {
-///     let settings = PrayerSettings::new(LatitudeMethod::OneSeventh, PrayerCalculationMethod::MWL, AsrCalculationMethod::Shafi);
-///     let month = get_prayer_times(&settings).await?;
+/// use ummah::{argparser::settings::PrayerSettings, core::get_prayer_times, types::UmmahResult};
+///
+/// async fn test(settings: &PrayerSettings) -> UmmahResult<()> {
+///     let month = get_prayer_times(settings, None).await?;
 ///     assert!(!month.is_empty());
 ///
 ///     // Print all days in the month
-///     for day in month {
+///     for day in month.iter() {
 ///         println!("{}", day);
 ///     }
 ///
@@ -50,13 +88,120 @@ static CURRENT_SETTINGS: &str = ".current_settings.yaml";
 /// ```
 pub async fn get_prayer_times(
     prayer_settings: &PrayerSettings,
-    custom_month: Option<u32>,
+    custom_month: Option<NaiveDate>,
 ) -> UmmahResult<Month> {
-    match (check_settings(prayer_settings), load_data(), custom_month) {
-        (_, _, Some(custom_month)) => request_times(prayer_settings, custom_month).await,
-        (true, Some(month), _) => Ok(month),
-        _ => request_times_now(prayer_settings).await,
+    let sources = prayer_settings.sources()?;
+    let sources = sources.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+    if let Some(custom_month) = custom_month {
+        return request_times(prayer_settings, &sources, custom_month).await;
+    }
+
+    let month = match load_current(prayer_settings, &sources[0].provider()) {
+        Some(month) => month,
+        None => request_times(prayer_settings, &sources, prayer_settings.today()).await?,
+    };
+
+    if prayer_settings.prefetch() && sources[0].is_cached() {
+        prefetch_next_month(prayer_settings, sources[0]).await;
     }
+
+    Ok(month)
+}
+
+/// Load the prayer times for the current month as last cached by [get_prayer_times]
+///
+/// Fails with [UmmahError::StaleCache] unless the cached month has the same settings,
+/// covers today and came from the first source in the settings.
+#[cfg(feature = "cache")]
+pub fn load_prayer_times(prayer_settings: &PrayerSettings) -> UmmahResult<Month> {
+    let sources = prayer_settings.sources()?;
+
+    load_current(prayer_settings, &sources[0].provider()).ok_or_else(|| UmmahError::StaleCache {
+        path: get_user_filepath().join(CURRENT_MONTH),
+    })
+}
+
+/// Calculate all prayer times for the current month without network access
+///
+/// Requires the location in the settings to have coordinates.
+pub fn calculate_prayer_times(
+    prayer_settings: &PrayerSettings,
+    custom_month: Option<NaiveDate>,
+) -> UmmahResult<Month> {
+    let date = custom_month.unwrap_or_else(|| prayer_settings.today());
+
+    let provider = CalculationSource.provider();
+    let mut month = calculate_month(prayer_settings, date)?;
+    month.set_provenance(&Provenance::new(Origin::Calculated, provider.clone()));
+
+    cache_data(&month, prayer_settings, &provider)?;
+
+    Ok(month)
+}
+
+/// Collect all prayer times for any range of dates, such as a week or a year
+///
+/// The range is fetched one calendar month at a time and merged, from the same
+/// sources as [get_prayer_times]. The times are not cached as the current month.
+pub async fn get_timetable(
+    prayer_settings: &PrayerSettings,
+    range: RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    let sources = prayer_settings.sources()?;
+    let sources = sources.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+
+    fetch_range(prayer_settings, &sources, &range).await
+}
+
+/// Collect all prayer times for any range of dates from the given source
+///
+/// Allows sources other than those in the settings, see [get_timetable].
+pub async fn get_timetable_from(
+    source: &dyn TimetableSource,
+    prayer_settings: &PrayerSettings,
+    range: RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    fetch_range(prayer_settings, &[source], &range).await
+}
+
+async fn fetch_range(
+    prayer_settings: &PrayerSettings,
+    sources: &[&dyn TimetableSource],
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    if range.is_empty() {
+        return Err(UmmahError::EmptyRange {
+            start: *range.start(),
+            end: *range.end(),
+        });
+    }
+
+    fetch_timetable(prayer_settings, sources, range).await
+}
+
+/// Collect all prayer times for the current Ramadan, or the next one if it has not begun
+///
+/// Ramadan usually spans two Gregorian months, which are fetched as in [get_timetable].
+/// The times are not cached.
+pub async fn get_ramadan_times(prayer_settings: &PrayerSettings) -> UmmahResult<Ramadan> {
+    let today = prayer_settings.today();
+    let (year, dates) =
+        ramadan::find_dates(prayer_settings, today).ok_or(UmmahError::Ramadan { date: today })?;
+
+    let timetable = get_timetable(prayer_settings, dates).await?;
+
+    Ok(Ramadan::new(year, timetable.iter(), prayer_settings))
+}
+
+/// Direction and distance to the Kaaba from the location in the settings
+///
+/// Requires the location in the settings to have coordinates.
+pub fn get_qibla(prayer_settings: &PrayerSettings) -> UmmahResult<Qibla> {
+    let location = prayer_settings.location();
+    let coordinates = location.coordinates.ok_or(UmmahError::Coordinates)?;
+
+    Ok(Qibla::new(coordinates, location.magnetic_declination))
 }
 
 /// Deletes all cached data
@@ -66,18 +211,22 @@ pub async fn get_prayer_times(
 ///  
 /// - [Documents](https://docs.rs/dirs-next/2.0.0/dirs_next/fn.document_dir.html)
 /// - [Cache](https://docs.rs/dirs-next/2.0.0/dirs_next/fn.cache_dir.html)
+#[cfg(feature = "cache")]
 pub fn clear_cache() -> UmmahResult<()> {
     let (docs, cache) = (get_user_filepath(), get_cache_filepath());
 
-    std::fs::remove_dir_all(docs)?;
-    std::fs::remove_dir_all(cache)?;
+    std::fs::remove_dir_all(&docs).map_err(UmmahError::file(&docs))?;
+    std::fs::remove_dir_all(&cache).map_err(UmmahError::file(&cache))?;
 
     Ok(())
 }
 
 /// Updates the timetable for a given day
+#[cfg(feature = "cache")]
 pub fn update_timetable(day: &Day) -> UmmahResult<()> {
-    let mut month = load_data().expect("Loading timetable");
+    let mut month = load_data().ok_or_else(|| UmmahError::StaleCache {
+        path: get_user_filepath().join(CURRENT_MONTH),
+    })?;
 
     month.update_day(day);
 
@@ -86,72 +235,203 @@ pub fn update_timetable(day: &Day) -> UmmahResult<()> {
     write_serialized_file(&docs, &PathBuf::from(CURRENT_MONTH), &month)
 }
 
-pub fn get_performed_status(date: chrono::NaiveDate, prayer_time: chrono::NaiveTime) -> bool {
-    let current_datetime = Local::now();
-    let current_date = current_datetime.date().naive_local();
-    let current_time = current_datetime.time();
-
-    match date.cmp(&current_date) {
-        std::cmp::Ordering::Less => true,
-        std::cmp::Ordering::Greater => false,
-        std::cmp::Ordering::Equal => prayer_time <= current_time,
-    }
+/// Checks if the moment of a prayer has passed, wherever the prayer takes place
+pub fn get_performed_status(prayer_time: DateTime<FixedOffset>) -> bool {
+    prayer_time.with_timezone(&Utc) <= Utc::now()
 }
 
-fn check_settings(prayer_settings: &PrayerSettings) -> bool {
+/// Loads the cached current timetable, if made by the provider with the same settings and covering today
+#[cfg(feature = "cache")]
+fn load_current(prayer_settings: &PrayerSettings, provider: &str) -> Option<Month> {
     let path = get_cache_filepath().join(CURRENT_SETTINGS);
-    match open_file(path) {
-        Err(_) => false,
-        Ok(file) => match serde_yaml::from_reader::<_, PrayerSettings>(file) {
-            Err(_) => false,
-            Ok(settings) => settings == prayer_settings.clone().with_audio_downloaded(),
-        },
-    }
+    let source = open_file(path)
+        .ok()
+        .and_then(|file| serde_yaml::from_reader::<_, CurrentSource>(file).ok())?;
+
+    let is_fresh = source.settings == prayer_settings.clone().with_audio_downloaded()
+        && source.provider == provider
+        && (source.start..=source.end).contains(&prayer_settings.today());
+
+    is_fresh.then(load_data).flatten()
 }
 
+/// Reads the stored current month, marking its days as cached
+#[cfg(feature = "cache")]
 fn load_data() -> Option<Month> {
     let path = get_user_filepath().join(CURRENT_MONTH);
-    open_file(path)
+    let mut month = open_file(path)
         .ok()
-        .and_then(|file| serde_yaml::from_reader::<_, Month>(file).ok())
+        .and_then(|file| serde_yaml::from_reader::<_, Month>(file).ok())?;
+
+    month.mark_cached();
+    Some(month)
+}
+
+/// Fetches the month containing the date and keeps it as the current month
+///
+/// The month is recorded under the provider it came from, so a month from a
+/// fallback source is fetched again from the preferred source next time.
+async fn request_times(
+    prayer_settings: &PrayerSettings,
+    sources: &[&dyn TimetableSource],
+    date: NaiveDate,
+) -> UmmahResult<Month> {
+    let month = fetch_timetable(prayer_settings, sources, &month_range(date)).await?;
+
+    let provider = month
+        .iter()
+        .find_map(Day::provenance)
+        .map(|provenance| provenance.provider.clone())
+        .unwrap_or_default();
+    cache_data(&month, prayer_settings, &provider)?;
+
+    Ok(month)
+}
+
+/// Stores next month's times in the [cache] during the last days of the month
+///
+/// Failures are ignored, as the month is downloaded again when it begins.
+#[cfg(feature = "cache")]
+async fn prefetch_next_month(prayer_settings: &PrayerSettings, source: &dyn TimetableSource) {
+    let today = prayer_settings.today();
+    let next_month = month_range(today).end().succ();
+    if next_month - today > Duration::days(PREFETCH_DAYS) {
+        return;
+    }
+
+    let _ = fetch_timetable(prayer_settings, &[source], &month_range(next_month)).await;
 }
 
-async fn request_times_now(prayer_settings: &PrayerSettings) -> UmmahResult<Month> {
-    let timetable = download_file(
-        prayer_settings.query(Local::now().date().naive_utc()),
-        "Downloading times",
-    )
-    .await?;
+/// Fetches each month of the range separately, as most sources serve one month at a time
+async fn fetch_timetable(
+    prayer_settings: &PrayerSettings,
+    sources: &[&dyn TimetableSource],
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    let mut months = Vec::new();
+    for part in split_by_month(range) {
+        months.push(fetch_with_fallback(prayer_settings, sources, &part).await?);
+    }
+
+    // Applied once the months are joined, so each month's last night can use the next morning
+    let mut timetable = Timetable::merge(months);
+    timetable.apply_settings(prayer_settings);
 
-    let month = parse_csv_file(timetable)?;
+    Ok(timetable)
+}
+
+/// Takes the dates, all in one month, from the first source that gives them
+///
+/// The sources after the first are only tried when those before them fail, and
+/// their times are marked as a fallback. If every source fails, the error of the
+/// first is returned.
+async fn fetch_with_fallback(
+    prayer_settings: &PrayerSettings,
+    sources: &[&dyn TimetableSource],
+    part: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    let mut error = None;
+    for (index, source) in sources.iter().enumerate() {
+        match fetch_month(prayer_settings, *source, part).await {
+            Ok(mut month) => {
+                if index > 0 {
+                    month.mark_fallback();
+                }
+                return Ok(month);
+            }
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
 
-    cache_data(&month, prayer_settings)?;
+    Err(error.unwrap_or(UmmahError::Source))
+}
+
+/// Takes the dates, all in one month, from the source
+///
+/// Months from cached sources are read through the cache, when there is one.
+async fn fetch_month(
+    prayer_settings: &PrayerSettings,
+    source: &dyn TimetableSource,
+    part: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    #[cfg(feature = "cache")]
+    if source.is_cached() {
+        return fetch_cached_month(prayer_settings, source, part).await;
+    }
+
+    let mut month = source.fetch(prayer_settings, part.clone()).await?;
+    month.set_provenance(&Provenance::new(source.origin(), source.provider()));
+    month.retain_range(part);
 
     Ok(month)
 }
 
-async fn request_times(prayer_settings: &PrayerSettings, month: u32) -> UmmahResult<Month> {
-    let timetable = download_file(
-        prayer_settings.query(chrono::NaiveDate::from_ymd(Local::now().year(), month, 1)),
-        "Downloading times",
-    )
-    .await?;
+/// Takes the dates, all in one month, from the [cache], fetching the whole month if it is not there
+#[cfg(feature = "cache")]
+async fn fetch_cached_month(
+    prayer_settings: &PrayerSettings,
+    source: &dyn TimetableSource,
+    part: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    // The key holds only what downloaded times depend on
+    assert_eq!(
+        source.origin(),
+        Origin::Downloaded,
+        "Caching {}",
+        source.provider()
+    );
 
-    let month = parse_csv_file(timetable)?;
+    let provider = source.provider();
+    let key = prayer_settings.cache_key(*part.start());
 
-    cache_data(&month, prayer_settings)?;
+    let mut month = match cache::get(&key, &provider) {
+        Some(month) => month,
+        None => {
+            let mut month = source
+                .fetch(prayer_settings, month_range(*part.start()))
+                .await?;
+            month.set_provenance(&Provenance::new(source.origin(), provider.clone()));
+            cache::insert(key, &provider, &month)?;
+            month
+        }
+    };
+    month.retain_range(part);
 
     Ok(month)
 }
 
-fn cache_data(days: &Month, prayer_settings: &PrayerSettings) -> UmmahResult<()> {
+#[cfg(feature = "cache")]
+fn cache_data(days: &Month, prayer_settings: &PrayerSettings, provider: &str) -> UmmahResult<()> {
+    let range = days.range().ok_or(UmmahError::EmptyTimetable)?;
+
     let (docs, cache) = (get_user_filepath(), get_cache_filepath());
     write_serialized_file(&docs, &PathBuf::from(CURRENT_MONTH), days)?;
     write_serialized_file(
         &cache,
         &PathBuf::from(CURRENT_SETTINGS),
-        &prayer_settings.clone().with_audio_downloaded(),
+        &CurrentSource {
+            settings: prayer_settings.clone().with_audio_downloaded(),
+            provider: provider.to_owned(),
+            start: *range.start(),
+            end: *range.end(),
+        },
     )?;
 
     Ok(())
 }
+
+/// Without the cache, no month is kept between calls
+#[cfg(not(feature = "cache"))]
+fn load_current(_: &PrayerSettings, _: &str) -> Option<Month> {
+    None
+}
+
+#[cfg(not(feature = "cache"))]
+fn cache_data(_: &Month, _: &PrayerSettings, _: &str) -> UmmahResult<()> {
+    Ok(())
+}
+
+#[cfg(not(feature = "cache"))]
+async fn prefetch_next_month(_: &PrayerSettings, _: &dyn TimetableSource) {}
@@ -0,0 +1,83 @@
+//! Blocking versions of the functions in [crate::core], for callers without an async runtime
+//!
+//! Each function runs its async counterpart on a runtime of its own, so they must
+//! not be called from within an async runtime. Reading the cache, parsing and
+//! calculating need no runtime at all, see [load_prayer_times], [calculate_prayer_times]
+//! and [crate::request_parser].
+//!
+//! # Example
+//! ```
+//! use ummah::{argparser::settings::PrayerSettings, core::blocking, types::UmmahResult};
+//!
+//! fn print_today(settings: &PrayerSettings) -> UmmahResult<()> {
+//!     let month = blocking::get_prayer_times(settings, None)?;
+//!
+//!     if let Some(today) = month.today(settings) {
+//!         println!("{}", today);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+pub use super::calculate_prayer_times;
+#[cfg(feature = "cache")]
+pub use super::load_prayer_times;
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    source::TimetableSource,
+    time::{month::Month, ramadan::Ramadan, timetable::Timetable},
+    types::{UmmahError, UmmahResult},
+};
+
+use chrono::NaiveDate;
+
+use std::{future::Future, ops::RangeInclusive};
+
+/// Collect all prayer times for the current month, or the month containing the custom date
+///
+/// See [super::get_prayer_times].
+pub fn get_prayer_times(
+    prayer_settings: &PrayerSettings,
+    custom_month: Option<NaiveDate>,
+) -> UmmahResult<Month> {
+    block_on(super::get_prayer_times(prayer_settings, custom_month))
+}
+
+/// Collect all prayer times for any range of dates, such as a week or a year
+///
+/// See [super::get_timetable].
+pub fn get_timetable(
+    prayer_settings: &PrayerSettings,
+    range: RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    block_on(super::get_timetable(prayer_settings, range))
+}
+
+/// Collect all prayer times for any range of dates from the given source
+///
+/// See [super::get_timetable_from].
+pub fn get_timetable_from(
+    source: &dyn TimetableSource,
+    prayer_settings: &PrayerSettings,
+    range: RangeInclusive<NaiveDate>,
+) -> UmmahResult<Timetable> {
+    block_on(super::get_timetable_from(source, prayer_settings, range))
+}
+
+/// Collect all prayer times for the current Ramadan, or the next one if it has not begun
+///
+/// See [super::get_ramadan_times].
+pub fn get_ramadan_times(prayer_settings: &PrayerSettings) -> UmmahResult<Ramadan> {
+    block_on(super::get_ramadan_times(prayer_settings))
+}
+
+/// Runs the future to completion on a single-threaded runtime
+fn block_on<T, F: Future<Output = UmmahResult<T>>>(future: F) -> UmmahResult<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(UmmahError::Runtime)?
+        .block_on(future)
+}
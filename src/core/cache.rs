@@ -0,0 +1,460 @@
+//! Store of downloaded timetables, kept for every location and month fetched
+//!
+//! Timetables are stored as downloaded, before the user's offsets and Hijri
+//! calendar are applied, so changing those does not need another download.
+//! An index in the cache directory records each entry and when it was last used.
+
+use crate::{
+    time::timetable::Timetable,
+    types::{
+        AsrMethod, Coordinates, LatitudeMethod, Origin, PrayerMethod, Provenance, UmmahError,
+        UmmahResult,
+    },
+};
+
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use std::{cmp::Reverse, path::PathBuf};
+
+use super::fs::{get_cache_filepath, open_file, write_serialized_file};
+
+static INDEX: &str = "index.yaml";
+static ENTRIES: &str = "timetables";
+
+/// Entries kept before the least recently used are evicted
+const MAX_ENTRIES: usize = 48;
+
+/// Bytes of timetables kept before the least recently used are evicted
+const MAX_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Days an entry is kept after it was last used
+const MAX_AGE_DAYS: i64 = 180;
+
+/// Everything a downloaded timetable depends on
+///
+/// Calculated times also depend on the elevation, the weather and the angles of a
+/// custom method, which the key leaves out, so only downloaded timetables are stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub(crate) country: String,
+    pub(crate) city: String,
+    #[serde(default)]
+    pub(crate) coordinates: Option<Coordinates>,
+    pub(crate) latitude: LatitudeMethod,
+    pub(crate) prayer: PrayerMethod,
+    pub(crate) asr: AsrMethod,
+    pub(crate) time_zone: Option<Tz>,
+    pub(crate) year: i32,
+    pub(crate) month: u32,
+}
+
+impl CacheKey {
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    /// City as named by www.salahtimes.com
+    pub fn city(&self) -> &str {
+        &self.city
+    }
+
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        self.coordinates
+    }
+
+    pub fn latitude_method(&self) -> LatitudeMethod {
+        self.latitude
+    }
+
+    pub fn prayer_method(&self) -> PrayerMethod {
+        self.prayer
+    }
+
+    pub fn asr_method(&self) -> AsrMethod {
+        self.asr
+    }
+
+    /// Time zone the times were read in, or the system's if none
+    pub fn time_zone(&self) -> Option<Tz> {
+        self.time_zone
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// Name of the file holding the entry's timetable
+    fn file_name(&self) -> String {
+        let coordinates = self.coordinates.map_or_else(String::new, |coordinates| {
+            format!("{:.4}_{:.4}", coordinates.latitude, coordinates.longitude)
+        });
+
+        format!(
+            "{}_{}_{}_{:?}_{:?}_{:?}_{}_{}-{:02}.yaml",
+            self.country,
+            self.city,
+            coordinates,
+            self.latitude,
+            self.prayer,
+            self.asr,
+            self.time_zone.map_or("local", |time_zone| time_zone.name()),
+            self.year,
+            self.month
+        )
+        .to_lowercase()
+        .replace('/', "+")
+    }
+}
+
+/// A timetable in the store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    key: CacheKey,
+    #[serde(default)]
+    provider: String,
+    file: String,
+    size: u64,
+    created: DateTime<Utc>,
+    last_used: DateTime<Utc>,
+}
+
+impl CacheEntry {
+    pub fn key(&self) -> &CacheKey {
+        &self.key
+    }
+
+    /// Source and version of the timetable, such as www.salahtimes.com/0.1.0
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    /// Size of the stored timetable, in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Moment the timetable was downloaded
+    pub fn created(&self) -> DateTime<Utc> {
+        self.created
+    }
+
+    /// Moment the timetable was last read from the store
+    pub fn last_used(&self) -> DateTime<Utc> {
+        self.last_used
+    }
+
+    /// Path of the file holding the timetable
+    pub fn path(&self) -> PathBuf {
+        get_cache_filepath().join(ENTRIES).join(&self.file)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: Vec<CacheEntry>,
+}
+
+/// Lists the timetables in the store, most recently used first
+pub fn entries() -> Vec<CacheEntry> {
+    let mut entries = load_index().entries;
+    entries.sort_by_key(|entry| Reverse(entry.last_used));
+    entries
+}
+
+/// Reads a stored timetable, as downloaded
+pub fn inspect(entry: &CacheEntry) -> UmmahResult<Timetable> {
+    let path = entry.path();
+    let file = open_file(&path)?;
+    serde_yaml::from_reader(file).map_err(UmmahError::yaml(path))
+}
+
+/// Removes a timetable from the store
+pub fn remove(key: &CacheKey) -> UmmahResult<()> {
+    let mut index = load_index();
+    if let Some(position) = index.entries.iter().position(|entry| entry.key == *key) {
+        let entry = index.entries.remove(position);
+        delete_entry(&entry);
+        save_index(&index)?;
+    }
+
+    Ok(())
+}
+
+/// Removes the timetables not used for the given time, returning how many were removed
+pub fn prune(max_age: Duration) -> UmmahResult<usize> {
+    let mut index = load_index();
+    let count = index.entries.len();
+
+    evict(&mut index, Utc::now() - max_age);
+    save_index(&index)?;
+
+    Ok(count - index.entries.len())
+}
+
+/// Gets a timetable stored by the provider, marking it as used
+///
+/// The days record that they were downloaded by the provider when the entry was created.
+/// Entries from another version of the provider are stale and left to be replaced.
+/// Entries whose file is missing or unreadable are dropped from the index.
+pub(crate) fn get(key: &CacheKey, provider: &str) -> Option<Timetable> {
+    let mut index = load_index();
+    let position = index
+        .entries
+        .iter()
+        .position(|entry| entry.key == *key && entry.provider == provider)?;
+
+    match inspect(&index.entries[position]) {
+        Ok(mut timetable) => {
+            let entry = &mut index.entries[position];
+            timetable.set_provenance(&Provenance {
+                origin: Origin::Cached,
+                provider: entry.provider.clone(),
+                obtained: entry.created,
+                fallback: false,
+            });
+            entry.last_used = Utc::now();
+            let _ = save_index(&index);
+            Some(timetable)
+        }
+        Err(_) => {
+            index.entries.remove(position);
+            let _ = save_index(&index);
+            None
+        }
+    }
+}
+
+/// Stores a timetable, replacing any with the same key, then evicts old entries
+pub(crate) fn insert(key: CacheKey, provider: &str, timetable: &Timetable) -> UmmahResult<()> {
+    let mut index = load_index();
+    index.entries.retain(|entry| entry.key != key);
+
+    let now = Utc::now();
+    let entry = CacheEntry {
+        file: key.file_name(),
+        key,
+        provider: provider.to_owned(),
+        size: 0,
+        created: now,
+        last_used: now,
+    };
+    write_serialized_file(
+        &get_cache_filepath().join(ENTRIES),
+        &PathBuf::from(&entry.file),
+        timetable,
+    )?;
+
+    let path = entry.path();
+    let size = std::fs::metadata(&path)
+        .map_err(UmmahError::file(&path))?
+        .len();
+    index.entries.push(CacheEntry { size, ..entry });
+
+    evict(&mut index, now - Duration::days(MAX_AGE_DAYS));
+    save_index(&index)
+}
+
+/// Drops entries unused since the oldest moment, then the least recently used while the store is too big
+fn evict(index: &mut CacheIndex, oldest: DateTime<Utc>) {
+    let (kept, expired) = index
+        .entries
+        .drain(..)
+        .partition::<Vec<_>, _>(|entry| entry.last_used >= oldest);
+    expired.iter().for_each(delete_entry);
+
+    index.entries = kept;
+    index.entries.sort_by_key(|entry| Reverse(entry.last_used));
+
+    let mut total = 0;
+    let mut count = 0;
+    let (kept, evicted) = index.entries.drain(..).partition::<Vec<_>, _>(|entry| {
+        total += entry.size;
+        count += 1;
+        count <= MAX_ENTRIES && total <= MAX_BYTES
+    });
+    evicted.iter().for_each(delete_entry);
+
+    index.entries = kept;
+}
+
+fn delete_entry(entry: &CacheEntry) {
+    let _ = std::fs::remove_file(entry.path());
+}
+
+fn load_index() -> CacheIndex {
+    open_file(get_cache_filepath().join(INDEX))
+        .ok()
+        .and_then(|file| serde_yaml::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(index: &CacheIndex) -> UmmahResult<()> {
+    write_serialized_file(&get_cache_filepath(), &PathBuf::from(INDEX), index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::fs::TEST_CACHE_DIR;
+
+    use std::fs;
+
+    /// Runs the test with an empty cache directory of its own
+    fn with_cache(name: &str, test: impl FnOnce()) {
+        let dir = std::env::temp_dir().join(format!("ummah-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        TEST_CACHE_DIR.with(|cache_dir| *cache_dir.borrow_mut() = Some(dir.clone()));
+
+        test();
+
+        TEST_CACHE_DIR.with(|cache_dir| *cache_dir.borrow_mut() = None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Key of the nth month from January 2000 in London
+    fn key(months: u32) -> CacheKey {
+        CacheKey {
+            country: "uk".to_owned(),
+            city: "london".to_owned(),
+            coordinates: None,
+            latitude: LatitudeMethod::OneSeventh,
+            prayer: PrayerMethod::MWL,
+            asr: AsrMethod::Shafi,
+            time_zone: Some(chrono_tz::Europe::London),
+            year: 2000 + (months / 12) as i32,
+            month: months % 12 + 1,
+        }
+    }
+
+    fn insert_month(months: u32, provider: &str) {
+        insert(key(months), provider, &Timetable::new(Vec::new())).unwrap();
+    }
+
+    fn cached_months() -> Vec<u32> {
+        let mut months = load_index()
+            .entries
+            .iter()
+            .map(|entry| (entry.key.year - 2000) as u32 * 12 + entry.key.month - 1)
+            .collect::<Vec<_>>();
+        months.sort_unstable();
+        months
+    }
+
+    /// Changes the entries of the months in the index
+    fn edit_entries(months: &[u32], edit: impl Fn(&mut CacheEntry)) {
+        let mut index = load_index();
+        index
+            .entries
+            .iter_mut()
+            .filter(|entry| months.iter().any(|&months| entry.key == key(months)))
+            .for_each(edit);
+        save_index(&index).unwrap();
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_beyond_the_limit() {
+        with_cache("lru", || {
+            for months in 0..MAX_ENTRIES as u32 {
+                insert_month(months, "test");
+            }
+            assert!(get(&key(0), "test").is_some());
+
+            insert_month(MAX_ENTRIES as u32, "test");
+
+            let months = cached_months();
+            assert_eq!(months.len(), MAX_ENTRIES);
+            assert!(months.contains(&0));
+            assert!(!months.contains(&1));
+            let file = |months: u32| {
+                get_cache_filepath()
+                    .join(ENTRIES)
+                    .join(key(months).file_name())
+            };
+            assert!(file(0).exists());
+            assert!(!file(1).exists());
+        });
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_beyond_the_size() {
+        with_cache("size", || {
+            for months in 0..3 {
+                insert_month(months, "test");
+            }
+            edit_entries(&[0, 1, 2], |entry| entry.size = MAX_BYTES / 3);
+            edit_entries(&[1], |entry| {
+                entry.last_used = Utc::now() - Duration::days(1)
+            });
+
+            insert_month(3, "test");
+
+            assert_eq!(cached_months(), vec![0, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn evicts_entries_unused_for_too_long() {
+        with_cache("age", || {
+            insert_month(0, "test");
+            insert_month(1, "test");
+            edit_entries(&[0], |entry| {
+                entry.last_used = Utc::now() - Duration::days(MAX_AGE_DAYS + 1)
+            });
+
+            insert_month(2, "test");
+
+            assert_eq!(cached_months(), vec![1, 2]);
+        });
+    }
+
+    #[test]
+    fn prunes_entries_unused_for_the_given_time() {
+        with_cache("prune", || {
+            for months in 0..3 {
+                insert_month(months, "test");
+            }
+            edit_entries(&[0, 2], |entry| {
+                entry.last_used = Utc::now() - Duration::days(10)
+            });
+
+            assert_eq!(prune(Duration::days(5)).unwrap(), 2);
+            assert_eq!(cached_months(), vec![1]);
+            assert_eq!(prune(Duration::days(5)).unwrap(), 0);
+        });
+    }
+
+    #[test]
+    fn replaces_entries_from_another_version_of_the_provider() {
+        with_cache("provider", || {
+            insert_month(0, "www.salahtimes.com 0.1.0");
+
+            assert!(get(&key(0), "www.salahtimes.com 0.2.0").is_none());
+            insert_month(0, "www.salahtimes.com 0.2.0");
+
+            let entries = entries();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].provider(), "www.salahtimes.com 0.2.0");
+            assert!(get(&key(0), "www.salahtimes.com 0.2.0").is_some());
+        });
+    }
+
+    #[test]
+    fn drops_unreadable_entries() {
+        with_cache("unreadable", || {
+            insert_month(0, "test");
+            insert_month(1, "test");
+            let entry = load_index().entries[0].clone();
+            fs::write(entry.path(), "- not a timetable").unwrap();
+
+            assert!(get(&entry.key, "test").is_none());
+            assert_eq!(entries().len(), 1);
+        });
+    }
+}
@@ -6,7 +6,6 @@ use serde::Serialize;
 
 use std::{
     fs::File,
-    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -17,29 +16,55 @@ pub fn get_user_filepath() -> PathBuf {
     dirs_next::document_dir().map_or_else(|| "adhan".into(), |dir| dir.join("adhan"))
 }
 
+#[cfg(test)]
+thread_local! {
+    /// Cache directory of the tests running on this thread, instead of the user's
+    pub(crate) static TEST_CACHE_DIR: std::cell::RefCell<Option<PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
 /// Gets cache directory for core files. Files are stored in "adhan" directory
 ///
 /// The cache directory differs between OSes.
 pub fn get_cache_filepath() -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = TEST_CACHE_DIR.with(|dir| dir.borrow().clone()) {
+        return dir;
+    }
+
     dirs_next::cache_dir().map_or_else(|| "adhan".into(), |dir| dir.join("adhan"))
 }
 
+/// Gets config directory for core files. Files are stored in "adhan" directory
+///
+/// The config directory differs between OSes.
+pub fn get_config_filepath() -> PathBuf {
+    dirs_next::config_dir().map_or_else(|| "adhan".into(), |dir| dir.join("adhan"))
+}
+
 pub(crate) fn open_file<P: AsRef<Path>>(path: P) -> UmmahResult<File> {
-    File::open(path).map_err(UmmahError::IO)
+    File::open(&path).map_err(UmmahError::file(path))
 }
 
+#[cfg(feature = "html")]
 pub(crate) fn write_file<P: AsRef<Path>>(dir: P, file: P, data: &[u8]) -> UmmahResult<()> {
+    use std::io::Write;
+
     create_dir(&dir)?;
 
     let path = dir.as_ref().join(file);
 
     println!("Writing file to {:?}", path);
 
-    let mut file = File::create(path).map_err(UmmahError::IO)?;
+    let mut file = File::create(&path).map_err(UmmahError::file(&path))?;
 
-    file.write(data).map(|_| ()).map_err(UmmahError::IO)
+    file.write(data).map(|_| ()).map_err(UmmahError::file(path))
 }
 
+/// Serializes the data to the file, replacing it whole
+///
+/// The data is written to a temporary file beside it, which is then renamed, so other
+/// processes never read a partly written file.
 pub(crate) fn write_serialized_file<P: AsRef<Path>, T: Serialize>(
     dir: P,
     file: P,
@@ -47,17 +72,29 @@ pub(crate) fn write_serialized_file<P: AsRef<Path>, T: Serialize>(
 ) -> UmmahResult<()> {
     create_dir(&dir)?;
 
-    let path = dir.as_ref().join(file);
-
-    println!("Serializing data to {:?}", path);
-
-    let mut file = File::create(path).map_err(UmmahError::IO)?;
-    serde_yaml::to_writer(&mut file, data).map_err(UmmahError::Serde)
+    let path = dir.as_ref().join(&file);
+    let temp_path = dir.as_ref().join(format!(
+        ".{}.{}.tmp",
+        file.as_ref().display(),
+        std::process::id()
+    ));
+
+    let written = File::create(&temp_path)
+        .map_err(UmmahError::file(&temp_path))
+        .and_then(|mut temp_file| {
+            serde_yaml::to_writer(&mut temp_file, data).map_err(UmmahError::yaml(&temp_path))
+        })
+        .and_then(|_| std::fs::rename(&temp_path, &path).map_err(UmmahError::file(&path)));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    written
 }
 
 fn create_dir<P: AsRef<Path>>(dir: P) -> UmmahResult<()> {
     if std::fs::read_dir(&dir).is_err() {
-        std::fs::create_dir_all(dir).map_err(UmmahError::IO)?;
+        std::fs::create_dir_all(&dir).map_err(UmmahError::file(dir))?;
     };
     Ok(())
 }
@@ -1,8 +1,9 @@
 //! Module for holding [Prayer] struct
 
-use crate::types::PrayerName;
+use crate::{time::zone, types::PrayerName};
 
-use chrono::NaiveTime;
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 
 use std::fmt;
@@ -11,7 +12,7 @@ use std::fmt;
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub struct Prayer {
     name: PrayerName,
-    time: NaiveTime,
+    time: DateTime<FixedOffset>,
     #[serde(skip)]
     performed: bool,
 }
@@ -19,11 +20,11 @@ pub struct Prayer {
 impl fmt::Display for Prayer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.name {
-            PrayerName::Fajr => write!(f, "Fajr: {}", self.time),
-            PrayerName::Dhuhr => write!(f, "Dhuhr: {}", self.time),
-            PrayerName::Asr => write!(f, "Asr: {}", self.time),
-            PrayerName::Maghrib => write!(f, "Maghrib: {}", self.time),
-            PrayerName::Isha => write!(f, "Isha: {}", self.time),
+            PrayerName::Fajr => write!(f, "Fajr: {}", self.get_time()),
+            PrayerName::Dhuhr => write!(f, "Dhuhr: {}", self.get_time()),
+            PrayerName::Asr => write!(f, "Asr: {}", self.get_time()),
+            PrayerName::Maghrib => write!(f, "Maghrib: {}", self.get_time()),
+            PrayerName::Isha => write!(f, "Isha: {}", self.get_time()),
         }
     }
 }
@@ -44,12 +45,31 @@ impl Prayer {
         self.name
     }
 
-    /// Gets the prayer time
+    /// Gets the prayer time on the clock of the timetable's time zone
     pub fn get_time(&self) -> NaiveTime {
+        self.time.time()
+    }
+
+    /// Gets the moment the prayer begins, with the offset of the timetable's time zone
+    pub fn get_datetime(&self) -> DateTime<FixedOffset> {
         self.time
     }
 
-    pub(crate) fn new(name: PrayerName, time: NaiveTime, performed: bool) -> Self {
+    /// Shows the prayer time on the clock of another time zone
+    pub fn in_time_zone<Z: TimeZone>(&self, time_zone: &Z) -> Self {
+        Self {
+            time: zone::fix(self.time.with_timezone(time_zone)),
+            ..*self
+        }
+    }
+
+    /// Moves the prayer time by the given number of minutes, keeping to the zone's clock
+    pub(crate) fn shift(&mut self, time_zone: Option<Tz>, minutes: i64) {
+        let time = self.time + chrono::Duration::minutes(minutes);
+        self.time = zone::from_utc(time_zone, time.naive_utc());
+    }
+
+    pub(crate) fn new(name: PrayerName, time: DateTime<FixedOffset>, performed: bool) -> Self {
         Self {
             name,
             time,
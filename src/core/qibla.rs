@@ -0,0 +1,140 @@
+//! Module for holding [Qibla] struct
+
+use crate::types::Coordinates;
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// Coordinates of the Kaaba in Makkah
+pub const KAABA: Coordinates = Coordinates {
+    latitude: 21.4225,
+    longitude: 39.8262,
+};
+
+/// Direction and distance to the Kaaba from a location
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Qibla {
+    bearing: f64,
+    magnetic_bearing: Option<f64>,
+    distance: f64,
+}
+
+impl fmt::Display for Qibla {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Qibla: {:.1}° from true north", self.bearing)?;
+        if let Some(magnetic_bearing) = self.magnetic_bearing {
+            write!(f, " ({:.1}° magnetic)", magnetic_bearing)?;
+        }
+        write!(f, ", {:.0} km to Makkah", self.distance)
+    }
+}
+
+impl Qibla {
+    /// Calculates the great-circle direction and distance to the Kaaba
+    ///
+    /// The magnetic declination is in degrees, positive when magnetic north
+    /// lies east of true north.
+    pub fn new(coordinates: Coordinates, magnetic_declination: Option<f64>) -> Self {
+        let (latitude, kaaba_latitude) = (
+            coordinates.latitude.to_radians(),
+            KAABA.latitude.to_radians(),
+        );
+        let longitude_difference = (KAABA.longitude - coordinates.longitude).to_radians();
+
+        let bearing = (longitude_difference.sin() * kaaba_latitude.cos())
+            .atan2(
+                latitude.cos() * kaaba_latitude.sin()
+                    - latitude.sin() * kaaba_latitude.cos() * longitude_difference.cos(),
+            )
+            .to_degrees()
+            .rem_euclid(360.0);
+
+        Self {
+            bearing,
+            magnetic_bearing: magnetic_declination
+                .map(|declination| (bearing - declination).rem_euclid(360.0)),
+            distance: coordinates.distance_to(KAABA),
+        }
+    }
+
+    /// Gets the initial bearing to the Kaaba, in degrees clockwise from true north
+    pub fn get_bearing(&self) -> f64 {
+        self.bearing
+    }
+
+    /// Gets the initial bearing to the Kaaba, in degrees clockwise from magnetic north
+    pub fn get_magnetic_bearing(&self) -> Option<f64> {
+        self.magnetic_bearing
+    }
+
+    /// Gets the distance to the Kaaba, in kilometres
+    pub fn get_distance(&self) -> f64 {
+        self.distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONDON: Coordinates = Coordinates {
+        latitude: 51.5074,
+        longitude: -0.1278,
+    };
+
+    const NEW_YORK: Coordinates = Coordinates {
+        latitude: 40.7128,
+        longitude: -74.0060,
+    };
+
+    fn assert_near(value: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn points_along_the_great_circle() {
+        let london = Qibla::new(LONDON, None);
+        assert_near(london.get_bearing(), 118.99, 0.01);
+        assert_near(london.get_distance(), 4794.0, 1.0);
+        assert_eq!(london.get_magnetic_bearing(), None);
+
+        // The great circle leaves New York heading north east, though Makkah lies south of it
+        let new_york = Qibla::new(NEW_YORK, None);
+        assert_near(new_york.get_bearing(), 58.48, 0.01);
+        assert_near(new_york.get_distance(), 10306.0, 1.0);
+    }
+
+    #[test]
+    fn turns_the_bearing_by_the_magnetic_declination() {
+        let east = Qibla::new(LONDON, Some(1.5))
+            .get_magnetic_bearing()
+            .unwrap();
+        assert_near(east, 117.49, 0.01);
+
+        let west = Qibla::new(NEW_YORK, Some(-12.9))
+            .get_magnetic_bearing()
+            .unwrap();
+        assert_near(west, 71.38, 0.01);
+    }
+
+    #[test]
+    fn points_south_from_due_north_and_north_of_east_from_due_west() {
+        let due_north = Coordinates {
+            latitude: 30.0,
+            longitude: KAABA.longitude,
+        };
+        assert_near(Qibla::new(due_north, None).get_bearing(), 180.0, 1e-9);
+
+        let west = Coordinates {
+            latitude: KAABA.latitude,
+            longitude: 30.0,
+        };
+        assert!((85.0..90.0).contains(&Qibla::new(west, None).get_bearing()));
+    }
+}
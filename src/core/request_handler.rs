@@ -1,30 +1,360 @@
-use std::io::Write;
+//! HTTP client used by the downloading sources
+//!
+//! Requests time out, are retried with exponential backoff when the network or
+//! server fails, and have their responses checked before they are parsed.
+
+use std::{io::Write, time::Duration};
 
 use bytes::Bytes;
-use reqwest::IntoUrl;
+use reqwest::{header::CONTENT_TYPE, Client, Proxy};
 
 use crate::types::{UmmahError, UmmahResult};
 
-pub async fn download_file<T: IntoUrl>(url: T, progress_message: &str) -> UmmahResult<Bytes> {
-    print!("{}...\r", progress_message);
-    std::io::stdout()
-        .flush()
-        .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
-    let data = request_file(url).await?;
-    print!("{:<32}\r", "");
-    std::io::stdout()
-        .flush()
-        .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
-    Ok(data)
+static USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+    " (prayer times; +https://github.com/N-Hoque/ummah)"
+);
+
+/// Seconds allowed to connect to the server
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+/// Seconds allowed for a whole request, from connecting to reading the body
+const TIMEOUT_SECS: u64 = 30;
+
+/// Attempts made before giving up on a request
+const ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry, doubled for each retry after it
+const BACKOFF_MILLIS: u64 = 500;
+
+/// What a response is expected to hold, checked before it is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Content {
+    /// A CSV timetable whose header begins with the `day` column
+    Csv,
+    /// A JSON object
+    Json,
+}
+
+impl Content {
+    /// Checks the type and beginning of a response, so error pages are not parsed as timetables
+    fn check(self, content_type: Option<&str>, body: &[u8]) -> Result<(), String> {
+        let content_type = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|content_type| content_type.trim().to_ascii_lowercase());
+        if let Some(content_type) = content_type.as_deref() {
+            if content_type == "text/html" || content_type == "application/xhtml+xml" {
+                return Err(format!("expected {} but got an HTML page", self));
+            }
+        }
+
+        let text = String::from_utf8_lossy(&body[..body.len().min(64)]);
+        let start = text.trim_start_matches('\u{feff}').trim_start();
+        let is_expected = match self {
+            Content::Csv => start
+                .get(..3)
+                .map_or(false, |column| column.eq_ignore_ascii_case("day")),
+            Content::Json => start.starts_with('{'),
+        };
+
+        if is_expected {
+            Ok(())
+        } else if start.starts_with('<') {
+            Err(format!("expected {} but got an HTML page", self))
+        } else {
+            Err(format!("expected {}", self))
+        }
+    }
+}
+
+impl std::fmt::Display for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Content::Csv => write!(f, "a CSV timetable"),
+            Content::Json => write!(f, "a JSON object"),
+        }
+    }
+}
+
+/// Client for downloading timetables
+///
+/// Proxies are taken from the `HTTP_PROXY` and `HTTPS_PROXY` environment
+/// variables, unless one is given in the settings.
+pub(crate) struct HttpClient {
+    client: Client,
+}
+
+impl HttpClient {
+    pub(crate) fn new(proxy: Option<&str>) -> UmmahResult<Self> {
+        HttpClient::with_timeout(proxy, Duration::from_secs(TIMEOUT_SECS))
+    }
+
+    /// Client whose requests must finish within the timeout
+    fn with_timeout(proxy: Option<&str>, timeout: Duration) -> UmmahResult<Self> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+            .timeout(timeout);
+
+        if let Some(proxy) = proxy {
+            let proxy = Proxy::all(proxy).map_err(|source| UmmahError::Proxy {
+                proxy: proxy.to_owned(),
+                source,
+            })?;
+            builder = builder.proxy(proxy);
+        }
+
+        let client = builder.build().map_err(UmmahError::Client)?;
+
+        Ok(Self { client })
+    }
+
+    /// Downloads the body at the URL, showing the message while it does
+    pub(crate) async fn download(
+        &self,
+        url: &str,
+        content: Content,
+        progress_message: &str,
+    ) -> UmmahResult<Bytes> {
+        print!("{}...\r", progress_message);
+        std::io::stdout().flush().map_err(UmmahError::Terminal)?;
+        let data = self.request_file(url, content).await;
+        print!("{:<32}\r", "");
+        std::io::stdout().flush().map_err(UmmahError::Terminal)?;
+        data
+    }
+
+    /// Requests the URL until it succeeds, fails for good or runs out of attempts
+    async fn request_file(&self, url: &str, content: Content) -> UmmahResult<Bytes> {
+        let mut attempt = 1;
+        loop {
+            match self.try_request_file(url, content).await {
+                Err(e) if attempt < ATTEMPTS && is_retryable(&e) => {}
+                result => return result,
+            }
+
+            let backoff = BACKOFF_MILLIS * 2u64.pow(attempt - 1);
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn try_request_file(&self, url: &str, content: Content) -> UmmahResult<Bytes> {
+        let network_error = |source| UmmahError::Network {
+            url: url.to_owned(),
+            source,
+        };
+
+        let response = self.client.get(url).send().await.map_err(network_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(UmmahError::Status {
+                url: url.to_owned(),
+                status: status.as_u16(),
+            });
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        let body = response.bytes().await.map_err(network_error)?;
+
+        content
+            .check(content_type.as_deref(), &body)
+            .map_err(|reason| UmmahError::Response {
+                url: url.to_owned(),
+                reason,
+            })?;
+
+        Ok(body)
+    }
 }
 
-async fn request_file<T: IntoUrl>(url: T) -> UmmahResult<Bytes> {
-    let response = reqwest::get(url)
-        .await
-        .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
-    let content = response
-        .bytes()
-        .await
-        .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
-    Ok(content)
+/// Checks if a request may succeed when tried again, as after a network or server failure
+///
+/// Only timeouts, failures to connect and bodies cut short are retried, not mistakes in
+/// the request itself such as an invalid URL. reqwest 0.11 has no separate read timeout,
+/// so a server that stops sending fails with the timeout of the whole request.
+fn is_retryable(error: &UmmahError) -> bool {
+    match error {
+        UmmahError::Network { source, .. } => {
+            source.is_timeout() || source.is_connect() || source.is_body()
+        }
+        UmmahError::Status { status, .. } => (500..600).contains(status),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    static TIMETABLE: &str = "day,fajr,sunrise,dhuhr,asr,maghrib,isha\n";
+
+    /// What the stand-in server does with a request
+    pub(crate) enum Reply {
+        Status(u16, &'static str, &'static str),
+        Silence(Duration),
+    }
+
+    /// Starts a server on 127.0.0.1 answering each request with the next reply,
+    /// returning its URL and the number of requests it was sent
+    pub(crate) fn serve(replies: Vec<Reply>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let count = Arc::clone(&requests);
+        thread::spawn(move || {
+            for (reply, stream) in replies.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                count.fetch_add(1, Ordering::SeqCst);
+
+                match reply {
+                    Reply::Status(status, content_type, body) => {
+                        let _ = write!(
+                            stream,
+                            "HTTP/1.1 {} Status\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            status,
+                            content_type,
+                            body.len(),
+                            body
+                        );
+                    }
+                    Reply::Silence(duration) => {
+                        thread::spawn(move || {
+                            thread::sleep(duration);
+                            drop(stream);
+                        });
+                    }
+                }
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn request(url: &str, timeout: Duration) -> UmmahResult<Bytes> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                HttpClient::with_timeout(None, timeout)?
+                    .request_file(url, Content::Csv)
+                    .await
+            })
+    }
+
+    #[test]
+    fn does_not_retry_missing_pages() {
+        let (url, requests) = serve(vec![
+            Reply::Status(404, "text/plain", "Not found"),
+            Reply::Status(200, "text/csv", TIMETABLE),
+        ]);
+
+        match request(&url, Duration::from_secs(5)) {
+            Err(UmmahError::Status { status: 404, .. }) => {}
+            result => panic!("Expected a 404 status, got {:?}", result),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, requests) = serve(vec![
+            Reply::Status(503, "text/plain", "Unavailable"),
+            Reply::Status(200, "text/csv", TIMETABLE),
+        ]);
+
+        let body = request(&url, Duration::from_secs(5)).unwrap();
+
+        assert_eq!(body, TIMETABLE.as_bytes());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn rejects_html_pages() {
+        let (url, requests) = serve(vec![Reply::Status(
+            200,
+            "text/html; charset=utf-8",
+            "<html><body>Maintenance</body></html>",
+        )]);
+
+        match request(&url, Duration::from_secs(5)) {
+            Err(UmmahError::Response { .. }) => {}
+            result => panic!("Expected an unexpected response, got {:?}", result),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn gives_up_on_silent_servers() {
+        let silence = Duration::from_secs(2);
+        let (url, requests) = serve((0..ATTEMPTS).map(|_| Reply::Silence(silence)).collect());
+
+        match request(&url, Duration::from_millis(200)) {
+            Err(UmmahError::Network { source, .. }) => assert!(source.is_timeout()),
+            result => panic!("Expected a network timeout, got {:?}", result),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), ATTEMPTS as usize);
+    }
+
+    #[test]
+    fn retries_only_failures_of_the_network_or_server() {
+        let network_error = |url: &str, source| UmmahError::Network {
+            url: url.to_owned(),
+            source,
+        };
+
+        let invalid = Client::new().get("not a url").build().unwrap_err();
+        assert!(!is_retryable(&network_error("not a url", invalid)));
+
+        // Nothing listens on the port once the listener is dropped
+        let url = format!(
+            "http://{}/",
+            TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+        );
+        let refused = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(Client::new().get(&url).send())
+            .unwrap_err();
+        assert!(is_retryable(&network_error(&url, refused)));
+
+        for (status, is_retried) in [(404, false), (429, false), (500, true), (503, true)] {
+            let error = UmmahError::Status {
+                url: url.clone(),
+                status,
+            };
+            assert_eq!(is_retryable(&error), is_retried, "HTTP status {}", status);
+        }
+    }
 }
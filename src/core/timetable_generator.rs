@@ -1,33 +1,49 @@
 //! Logic for generating timetable
 
 use crate::{
-    time::month::Month,
-    types::{UmmahError, UmmahResult},
+    core::timing::Timing,
+    time::{day::Day, month::Month, ramadan::Ramadan},
+    types::{PrayerName, TimingName, UmmahError, UmmahResult},
 };
 
-use chrono::{Datelike, NaiveDate};
+use chrono::Datelike;
 use html_builder::Html5;
 
 use std::{fmt::Write, path::PathBuf};
 
-use super::fs::{get_user_filepath, write_file};
+use super::{
+    fs::{get_user_filepath, write_file},
+    qibla::Qibla,
+};
 
 static CURRENT_HTML: &str = "current_month.html";
+static RAMADAN_HTML: &str = "ramadan.html";
+
+static FALLBACK_NOTE: &str =
+    "† Times from a fallback source, as the preferred sources could not be used";
 
 pub struct TimetableGenerator {
     generate_css: bool,
-    custom_month: Option<u32>,
+    qibla: Option<Qibla>,
 }
 
 impl TimetableGenerator {
-    pub fn new(generate_css: bool, custom_month: Option<u32>) -> Self {
+    pub fn new(generate_css: bool) -> Self {
         Self {
             generate_css,
-            custom_month,
+            qibla: None,
         }
     }
 
-    /// Creates an HTML page for the prayer timetable
+    /// Adds the Qibla direction under the timetable's heading
+    pub fn with_qibla(self, qibla: Qibla) -> Self {
+        Self {
+            qibla: Some(qibla),
+            ..self
+        }
+    }
+
+    /// Creates an HTML page for the prayer timetable, whether of a month or any other range
     pub fn generate(&self, month: &Month) -> UmmahResult<()> {
         let mut document = html_builder::Buffer::new();
 
@@ -36,15 +52,27 @@ impl TimetableGenerator {
         TimetableGenerator::create_title(&mut html)?;
         self.create_table(&mut html, month)?;
 
+        self.write_document(CURRENT_HTML, document)
+    }
+
+    /// Creates an HTML page for the Suhoor and Iftar timetable of Ramadan
+    pub fn generate_ramadan(&self, ramadan: &Ramadan) -> UmmahResult<()> {
+        let mut document = html_builder::Buffer::new();
+
+        let mut html = document.html().attr("lang=en-gb");
+
+        TimetableGenerator::create_title(&mut html)?;
+        self.create_ramadan_table(&mut html, ramadan)?;
+
+        self.write_document(RAMADAN_HTML, document)
+    }
+
+    fn write_document(&self, file: &str, document: html_builder::Buffer) -> UmmahResult<()> {
         let final_document = document.finish();
 
         let user_path = get_user_filepath();
 
-        write_file(
-            &user_path,
-            &PathBuf::from(CURRENT_HTML),
-            final_document.as_bytes(),
-        )?;
+        write_file(&user_path, &PathBuf::from(file), final_document.as_bytes())?;
 
         if self.generate_css {
             TimetableGenerator::generate_default_css()?;
@@ -58,18 +86,42 @@ impl TimetableGenerator {
     fn create_table(&self, html: &mut html_builder::Node, month: &Month) -> UmmahResult<()> {
         let mut body = html.body();
 
-        writeln!(body.h1(), "Adhan").map_err(|x| UmmahError::Unknown(Box::new(x)))?;
+        writeln!(body.h1(), "Adhan")?;
+
+        if let Some(qibla) = self.qibla {
+            writeln!(body.p().attr("class='qibla'"), "{}", qibla)?;
+        }
+
+        if month.is_fallback() {
+            writeln!(body.p().attr("class='fallback'"), "{}", FALLBACK_NOTE)?;
+        }
+
+        let mut timings = month
+            .iter()
+            .flat_map(|day| day.get_timings().iter().map(Timing::get_name))
+            .collect::<Vec<_>>();
+        timings.sort();
+        timings.dedup();
+
+        let range = month.range().ok_or(UmmahError::EmptyTimetable)?;
+        let is_one_month = (range.start().year(), range.start().month())
+            == (range.end().year(), range.end().month());
+        let (title, date_format) = if is_one_month {
+            (range.start().format("%b %Y").to_string(), "%A, %d")
+        } else {
+            (
+                format!(
+                    "{} - {}",
+                    range.start().format("%d %b %Y"),
+                    range.end().format("%d %b %Y")
+                ),
+                "%a %d %b %Y",
+            )
+        };
 
         let mut table = body.table().attr("class='tg'");
-        TimetableGenerator::create_table_header(
-            &mut table,
-            if let Some(month) = self.custom_month {
-                chrono::NaiveDate::from_ymd(chrono::Local::now().year(), month, 1)
-            } else {
-                month.today().unwrap().get_date()
-            },
-        )?;
-        TimetableGenerator::create_table_body(&mut table, month)?;
+        TimetableGenerator::create_table_header(&mut table, &title, &timings)?;
+        TimetableGenerator::create_table_body(&mut table, month, date_format, &timings)?;
         Ok(())
     }
 
@@ -79,48 +131,178 @@ impl TimetableGenerator {
             .link()
             .attr("rel='stylesheet'")
             .attr("href='current_month.css'");
-        writeln!(head.title(), "Adhan - Prayer Time Collector")
-            .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
+        writeln!(head.title(), "Adhan - Prayer Time Collector")?;
+        Ok(())
+    }
+
+    fn create_ramadan_table(
+        &self,
+        html: &mut html_builder::Node,
+        ramadan: &Ramadan,
+    ) -> UmmahResult<()> {
+        let mut body = html.body();
+
+        writeln!(body.h1(), "Ramadan {} AH", ramadan.year())?;
+
+        if let Some(qibla) = self.qibla {
+            writeln!(body.p().attr("class='qibla'"), "{}", qibla)?;
+        }
+
+        if ramadan.iter().any(|fast| fast.get_day().is_fallback()) {
+            writeln!(body.p().attr("class='fallback'"), "{}", FALLBACK_NOTE)?;
+        }
+
+        let mut table = body.table().attr("class='tg'");
+
+        let mut table_header = table.thead();
+        let mut header_row = table_header.tr();
+        for elem in [
+            "Ramadan", "Date", "Suhoor", "Fajr", "Iftar", "Isha", "Taraweeh", "Fast",
+        ] {
+            writeln!(header_row.th().attr("class='tg-baqh'"), "{}", elem)?;
+        }
+
+        let mut table_body = table.tbody();
+        for fast in ramadan.iter() {
+            let day = fast.get_day();
+            let duration = fast.duration();
+            let mut data_row = table_body.tr();
+            writeln!(data_row.td().attr("class='tg-baqh'"), "{}", fast.number())?;
+            TimetableGenerator::create_date_cell(&mut data_row, day, "%a %d %b")?;
+            for (class, value) in [
+                (
+                    "tg-baqh tg-fast",
+                    fast.suhoor_end().format("%k:%M").to_string(),
+                ),
+                (
+                    "tg-baqh",
+                    day.get_prayer(PrayerName::Fajr)
+                        .get_time()
+                        .format("%k:%M")
+                        .to_string(),
+                ),
+                ("tg-baqh tg-fast", fast.iftar().format("%k:%M").to_string()),
+                (
+                    "tg-baqh",
+                    day.get_prayer(PrayerName::Isha)
+                        .get_time()
+                        .format("%k:%M")
+                        .to_string(),
+                ),
+                (
+                    "tg-baqh tg-timing",
+                    fast.taraweeh()
+                        .map(|time| time.format("%k:%M").to_string())
+                        .unwrap_or_default(),
+                ),
+                (
+                    "tg-baqh tg-timing",
+                    format!(
+                        "{}h {:02}m",
+                        duration.num_hours(),
+                        duration.num_minutes() % 60
+                    ),
+                ),
+            ] {
+                writeln!(
+                    data_row.td().attr(&format!("class='{}'", class)),
+                    "{}",
+                    value
+                )?;
+            }
+        }
+
         Ok(())
     }
 
-    fn create_table_body(table: &mut html_builder::Node, month: &Month) -> UmmahResult<()> {
+    fn create_table_body(
+        table: &mut html_builder::Node,
+        month: &Month,
+        date_format: &str,
+        timings: &[TimingName],
+    ) -> UmmahResult<()> {
         let mut table_body = table.tbody();
         for day in month.iter() {
             let mut data_row = table_body.tr();
+            TimetableGenerator::create_date_cell(&mut data_row, day, date_format)?;
             writeln!(
                 data_row.td().attr("class='tg-baqh'"),
                 "{}",
-                day.get_date().format("%A, %d")
-            )
-            .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
+                day.hijri_date()
+            )?;
             for prayer in day.get_prayers() {
-                writeln!(
-                    data_row.td().attr("class='tg-baqh'"),
-                    "{}",
-                    prayer.get_time().format("%k:%M")
-                )
-                .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
+                match day.get_adjustment() {
+                    Some(adjustment) if day.is_adjusted(prayer.get_name()) => writeln!(
+                        data_row
+                            .td()
+                            .attr("class='tg-baqh tg-adjusted'")
+                            .attr(&format!(
+                                "title='{}'",
+                                escape_attribute(&adjustment.to_string())
+                            )),
+                        "{}*",
+                        prayer.get_time().format("%k:%M")
+                    ),
+                    _ => writeln!(
+                        data_row.td().attr("class='tg-baqh'"),
+                        "{}",
+                        prayer.get_time().format("%k:%M")
+                    ),
+                }?;
+            }
+            for name in timings {
+                let time = day
+                    .get_timing(*name)
+                    .map(|timing| timing.get_time().format("%k:%M").to_string())
+                    .unwrap_or_default();
+                writeln!(data_row.td().attr("class='tg-baqh tg-timing'"), "{}", time)?;
             }
         }
         Ok(())
     }
 
+    /// Writes the day's date, marked with where its times came from if from a fallback source
+    fn create_date_cell(
+        data_row: &mut html_builder::Node,
+        day: &Day,
+        date_format: &str,
+    ) -> UmmahResult<()> {
+        let date = day.get_date().format(date_format);
+        match day.provenance().filter(|_| day.is_fallback()) {
+            Some(provenance) => writeln!(
+                data_row
+                    .td()
+                    .attr("class='tg-baqh tg-fallback'")
+                    .attr(&format!(
+                        "title='{}'",
+                        escape_attribute(&provenance.to_string())
+                    )),
+                "{}†",
+                date
+            ),
+            None => writeln!(data_row.td().attr("class='tg-baqh'"), "{}", date),
+        }?;
+
+        Ok(())
+    }
+
     fn create_table_header(
         table: &mut html_builder::Node,
-        current_month: NaiveDate,
+        title: &str,
+        timings: &[TimingName],
     ) -> UmmahResult<()> {
         let mut table_header = table.thead();
         let mut header_row = table_header.tr();
-        writeln!(
-            header_row.th().attr("class='tg-baqh'"),
-            "{}",
-            current_month.format("%b %Y")
-        )
-        .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
-        for elem in ["Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"] {
-            writeln!(header_row.th().attr("class='tg-baqh'"), "{}", elem)
-                .map_err(|x| UmmahError::Unknown(Box::new(x)))?;
+        writeln!(header_row.th().attr("class='tg-baqh'"), "{}", title)?;
+        for elem in ["Hijri", "Fajr", "Dhuhr", "Asr", "Maghrib", "Isha"] {
+            writeln!(header_row.th().attr("class='tg-baqh'"), "{}", elem)?;
+        }
+        for name in timings {
+            writeln!(
+                header_row.th().attr("class='tg-baqh tg-timing'"),
+                "{}",
+                name
+            )?;
         }
         Ok(())
     }
@@ -128,12 +310,18 @@ impl TimetableGenerator {
     fn generate_default_css() -> UmmahResult<()> {
         let css = r#"
     h1 {font-family:Arial, sans-serif;text-align:center;}
+.qibla {font-family:Arial, sans-serif;text-align:center;color:#444;}
+.fallback {font-family:Arial, sans-serif;font-size:12px;text-align:center;color:#777;}
 .tg {border-collapse:collapse;border-color:#9ABAD9;border-spacing:0;width:100%}
 .tg td {background-color:#EBF5FF;border-color:#9ABAD9;border-style:solid;border-width:1px;color:#444;
   font-family:Arial, sans-serif;font-size:14px;overflow:hidden;padding:5px 20px;word-break:normal;text-align:center;}
 .tg th {background-color:#409cff;border-color:#9ABAD9;border-style:solid;border-width:1px;color:#fff;
   font-family:Arial, sans-serif;font-size:14px;font-weight:normal;overflow:hidden;padding:5px 20px;word-break:normal;}
 .tg tg-baqh{text-align:center;vertical-align:top}
+.tg .tg-adjusted{font-style:italic;}
+.tg td.tg-fallback{color:#777;}
+.tg td.tg-timing{background-color:#F5FAFF;color:#777;}
+.tg td.tg-fast{font-weight:bold;}
     "#;
 
         write_file(
@@ -148,10 +336,16 @@ impl TimetableGenerator {
 
         let css = r#"
 h1 {font-family:Arial, sans-serif;text-align:center;}
+.qibla {}
+.fallback {}
 .tg {}
 .tg td {}
 .tg th {}
 .tg tg-baqh{}
+.tg .tg-adjusted{}
+.tg .tg-fallback{}
+.tg .tg-timing{}
+.tg .tg-fast{}
     "#;
 
         write_file(
@@ -161,3 +355,19 @@ h1 {font-family:Arial, sans-serif;text-align:center;}
         )
     }
 }
+
+/// Escapes the characters with a meaning in HTML, so the text can be put in a quoted attribute
+fn escape_attribute(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
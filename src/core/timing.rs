@@ -0,0 +1,50 @@
+//! Module for holding [Timing] struct
+
+use crate::{time::zone, types::TimingName};
+
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// Represents a time of the day other than an obligatory prayer
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    name: TimingName,
+    time: DateTime<FixedOffset>,
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.get_time())
+    }
+}
+
+impl Timing {
+    /// Gets the timing name
+    pub fn get_name(&self) -> TimingName {
+        self.name
+    }
+
+    /// Gets the time on the clock of the timetable's time zone
+    pub fn get_time(&self) -> NaiveTime {
+        self.time.time()
+    }
+
+    /// Gets the moment of the timing, which may fall on the following date
+    pub fn get_datetime(&self) -> DateTime<FixedOffset> {
+        self.time
+    }
+
+    /// Shows the time on the clock of another time zone
+    pub fn in_time_zone<Z: TimeZone>(&self, time_zone: &Z) -> Self {
+        Self {
+            time: zone::fix(self.time.with_timezone(time_zone)),
+            ..*self
+        }
+    }
+
+    pub(crate) fn new(name: TimingName, time: DateTime<FixedOffset>) -> Self {
+        Self { name, time }
+    }
+}
@@ -0,0 +1,208 @@
+//! Places known without network access
+//!
+//! Links the cities used by www.salahtimes.com with the coordinates
+//! and time zone needed to calculate times locally. The table is stored
+//! compressed, with the columns `name`, `aliases` (separated by `;`),
+//! `country`, `slug`, `latitude`, `longitude` and `time_zone`.
+
+use crate::types::Coordinates;
+
+use chrono_tz::Tz;
+use flate2::read::GzDecoder;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+
+use std::{cmp::Ordering, fmt};
+
+/// Compressed table of places, one per row
+static PLACES: &[u8] = include_bytes!("gazetteer/places.csv.gz");
+
+/// Places read from the table when first needed
+static PARSED_PLACES: Lazy<Vec<Place>> = Lazy::new(|| read_places().expect("Reading gazetteer"));
+
+/// Lowest similarity for a place to be suggested by [search]
+const MIN_SCORE: f64 = 0.85;
+
+/// A city or town in the gazetteer
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Place {
+    name: String,
+    aliases: String,
+    country: String,
+    slug: Option<String>,
+    latitude: f64,
+    longitude: f64,
+    time_zone: Tz,
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.name, self.country.to_uppercase())
+    }
+}
+
+impl Place {
+    /// Gets the name of the place
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the other names the place is known by
+    pub fn aliases(&self) -> impl Iterator<Item = &str> {
+        self.aliases.split(';').filter(|alias| !alias.is_empty())
+    }
+
+    /// Gets the country, as used by www.salahtimes.com
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    /// Gets the city used by www.salahtimes.com, if the website has a page for the place
+    pub fn slug(&self) -> Option<&str> {
+        self.slug.as_deref()
+    }
+
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates {
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+
+    /// Gets the IANA time zone of the place
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone
+    }
+
+    /// Every name the place can be searched by
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str())
+            .chain(self.slug())
+            .chain(self.aliases())
+    }
+
+    /// Similarity of the closest of the place's names to a normalised query
+    fn score(&self, query: &str) -> f64 {
+        self.names()
+            .map(|name| strsim::jaro_winkler(query, &normalise(name)))
+            .fold(0.0, f64::max)
+    }
+
+    fn is_in(&self, country: &str) -> bool {
+        self.country.eq_ignore_ascii_case(country)
+    }
+}
+
+/// A place found by [search], with how closely it matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    place: Place,
+    score: f64,
+}
+
+impl Match {
+    pub fn place(&self) -> &Place {
+        &self.place
+    }
+
+    /// Gets the similarity to the search, from 0 to 1
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Checks if one of the place's names matched the search exactly
+    pub fn is_exact(&self) -> bool {
+        self.score >= 1.0
+    }
+}
+
+/// Gets every place in the gazetteer
+pub fn places() -> &'static [Place] {
+    &PARSED_PLACES
+}
+
+/// Finds a place in a country by its name, slug or one of its aliases
+///
+/// Case, punctuation and spacing are ignored, so "st albans" finds St Albans.
+pub fn find(country: &str, city: &str) -> Option<Place> {
+    let city = normalise(city);
+
+    places()
+        .iter()
+        .find(|place| place.is_in(country) && place.names().any(|name| normalise(name) == city))
+        .cloned()
+}
+
+/// Finds the places whose names are similar to the query, closest first
+///
+/// Only places in the given country are searched, if one is given.
+pub fn search(query: &str, country: Option<&str>) -> Vec<Match> {
+    let query = normalise(query);
+
+    let mut matches = places()
+        .iter()
+        .filter(|place| country.map_or(true, |country| place.is_in(country)))
+        .map(|place| Match {
+            score: place.score(&query),
+            place: place.clone(),
+        })
+        .filter(|found| found.score >= MIN_SCORE)
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|lhs, rhs| {
+        rhs.score
+            .partial_cmp(&lhs.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| lhs.place.name.cmp(&rhs.place.name))
+    });
+
+    matches
+}
+
+/// Finds the nearest place with a page on www.salahtimes.com
+pub fn nearest(coordinates: Coordinates) -> Option<Place> {
+    places()
+        .iter()
+        .filter(|place| place.slug.is_some())
+        .map(|place| (coordinates.distance_to(place.coordinates()), place))
+        .min_by(|(lhs, _), (rhs, _)| lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal))
+        .map(|(_, place)| place.clone())
+}
+
+/// Reads every place from the compressed table
+fn read_places() -> Result<Vec<Place>, csv::Error> {
+    csv::Reader::from_reader(GzDecoder::new(PLACES))
+        .deserialize()
+        .collect()
+}
+
+/// Lowercases a name and replaces punctuation with single spaces
+fn normalise(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_embedded_places() {
+        let places = read_places().unwrap();
+
+        assert!(!places.is_empty());
+        assert!(places
+            .iter()
+            .any(|place| place.slug.as_deref() == Some("london")));
+    }
+}
@@ -5,10 +5,26 @@
 //! Ummah is a library for obtaining the prayer times from [www.salahtimes.com/uk](www.salahtimes.com/uk)
 //!
 //! It provides support for settings prayer time calculations for
-//! different schools of thought.
+//! different schools of thought. Times can also be calculated locally
+//! from a location's coordinates when no network is available.
+//!
+//! ## Features
+//!
+//! The default features keep the library lean, with downloading and caching but no
+//! command line or HTML export. Without any, it holds only the time model, the
+//! calculation and the CSV parser.
+//!
+//! - `network`: downloading from www.salahtimes.com and JSON APIs, and `core::blocking`
+//! - `cache`: keeping timetables and settings in the user's cache directory
+//! - `cli`: the command line arguments in `argparser::arguments`
+//! - `html`: exporting HTML timetables with `core::timetable_generator`
+//! - `full`: all of the above
 
 pub mod argparser;
+pub mod calculator;
 pub mod core;
-pub(crate) mod request_parser;
+pub mod gazetteer;
+pub mod request_parser;
+pub mod source;
 pub mod time;
 pub mod types;
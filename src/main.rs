@@ -0,0 +1,241 @@
+//! Command line interface for the prayer times
+//!
+//! Arguments not given are taken from the defaults set with `ummah config set`.
+//! Failures are reported with their causes, and exit with a code for their kind:
+//! 2 for settings, 3 for the network, 4 for timetables that cannot be read,
+//! 5 for calculation, 6 for the cache and 7 for files and the terminal.
+
+use std::{error::Error, process};
+
+use chrono::{Duration, NaiveDate, Utc};
+use clap::Parser;
+use ummah::{
+    argparser::{
+        arguments::{CacheCommand, Command, ConfigCommand, PrayerArguments, TimesCommand},
+        config::Config,
+        settings::PrayerSettings,
+    },
+    core::{
+        self, cache, get_performed_status, prayer::Prayer, timetable_generator::TimetableGenerator,
+    },
+    gazetteer,
+    time::timetable::Timetable,
+    types::{ErrorKind, UmmahError, UmmahResult},
+};
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+
+        let mut source = error.source();
+        while let Some(cause) = source {
+            eprintln!("  caused by: {}", cause);
+            source = cause.source();
+        }
+
+        process::exit(exit_code(error.kind()));
+    }
+}
+
+fn run() -> UmmahResult<()> {
+    let mut config = Config::load()?;
+    let args = PrayerArguments::parse_from(config.apply(std::env::args_os()));
+
+    match args.command() {
+        Command::Search { query } => {
+            search(&query, args.country());
+            Ok(())
+        }
+        Command::Cache(command) => run_cache(command),
+        Command::Config(ConfigCommand::Show) => show_config(&config, &args),
+        Command::Config(ConfigCommand::Set { key, value }) => {
+            config.set(&key, &value)?;
+            config.save()
+        }
+        Command::Times(command) => {
+            let settings = args.settings()?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(UmmahError::Runtime)?;
+
+            runtime.block_on(run_times(&args, &settings, command))
+        }
+    }
+}
+
+/// Runs the commands that get times
+async fn run_times(
+    args: &PrayerArguments,
+    settings: &PrayerSettings,
+    command: TimesCommand,
+) -> UmmahResult<()> {
+    match command {
+        TimesCommand::Today => {
+            let month = core::get_prayer_times(settings, None).await?;
+            let today = month.today(settings).ok_or(UmmahError::MissingDate {
+                date: settings.today(),
+            })?;
+            println!("{}", today);
+        }
+        TimesCommand::Next => {
+            let today = settings.today();
+            let month = core::get_prayer_times(settings, None).await?;
+            let prayer = match next_prayer(&month, today) {
+                Some(prayer) => prayer,
+                None => {
+                    let tomorrow = today.succ();
+                    let timetable = core::get_timetable(settings, tomorrow..=tomorrow).await?;
+                    next_prayer(&timetable, tomorrow)
+                        .ok_or(UmmahError::MissingDate { date: tomorrow })?
+                }
+            };
+            show_next_prayer(&prayer);
+        }
+        TimesCommand::Month { month, ramadan } => {
+            if ramadan {
+                print!("{}", core::get_ramadan_times(settings).await?);
+            } else {
+                let custom_month = month.custom_month(settings.today());
+                show_days(&core::get_prayer_times(settings, custom_month).await?);
+            }
+        }
+        TimesCommand::Range(range) => {
+            show_days(&core::get_timetable(settings, range.date_range()).await?);
+        }
+        TimesCommand::Export(export) => {
+            let mut generator = TimetableGenerator::new(export.generate_default_css());
+            if args.show_qibla() {
+                generator = generator.with_qibla(core::get_qibla(settings)?);
+            }
+
+            if export.is_ramadan() {
+                generator.generate_ramadan(&core::get_ramadan_times(settings).await?)?;
+            } else if let Some(range) = export.date_range() {
+                generator.generate(&core::get_timetable(settings, range).await?)?;
+            } else {
+                let custom_month = export.custom_month(settings.today());
+                generator.generate(&core::get_prayer_times(settings, custom_month).await?)?;
+            }
+            return Ok(());
+        }
+    }
+
+    if args.show_qibla() {
+        println!("{}", core::get_qibla(settings)?);
+    }
+
+    Ok(())
+}
+
+fn run_cache(command: CacheCommand) -> UmmahResult<()> {
+    match command {
+        CacheCommand::List => {
+            let entries = cache::entries();
+            if entries.is_empty() {
+                println!("No timetables are cached");
+            }
+
+            for entry in entries {
+                let key = entry.key();
+                println!(
+                    "{}-{:02} {}/{} {:?}/{:?}/{:?} from {}, {} bytes, last used {}",
+                    key.year(),
+                    key.month(),
+                    key.country(),
+                    key.city(),
+                    key.prayer_method(),
+                    key.latitude_method(),
+                    key.asr_method(),
+                    entry.provider(),
+                    entry.size(),
+                    entry.last_used().format("%d %b %Y %H:%M UTC"),
+                );
+            }
+            Ok(())
+        }
+        CacheCommand::Clear => core::clear_cache(),
+        CacheCommand::Prune { days } => {
+            let removed = cache::prune(Duration::days(days))?;
+            println!("Removed {} cached timetables", removed);
+            Ok(())
+        }
+    }
+}
+
+fn search(query: &str, country: Option<&str>) {
+    let matches = gazetteer::search(query, country);
+    if matches.is_empty() {
+        println!("No places match {:?}", query);
+    }
+
+    for found in matches {
+        let place = found.place();
+        match place.slug() {
+            Some(slug) => println!("{} (--country {} --city {})", place, place.country(), slug),
+            None => println!("{} (calculated only)", place),
+        }
+    }
+}
+
+fn show_config(config: &Config, args: &PrayerArguments) -> UmmahResult<()> {
+    println!("Defaults from {}", Config::path().display());
+
+    let mut defaults = config.iter().peekable();
+    if defaults.peek().is_none() {
+        println!("No defaults are set");
+    }
+
+    for (key, value) in defaults {
+        println!("{} = {}", key, value);
+    }
+
+    let settings = args.settings()?;
+    let settings = serde_yaml::to_string(&settings).map_err(|source| UmmahError::Yaml {
+        path: Config::path(),
+        source,
+    })?;
+    print!(
+        "\nSettings they give\n{}",
+        settings.trim_start_matches("---\n")
+    );
+
+    Ok(())
+}
+
+fn show_days(timetable: &Timetable) {
+    for day in timetable.iter() {
+        println!("{}", day);
+    }
+}
+
+fn show_next_prayer(prayer: &Prayer) {
+    let left = prayer.get_datetime().with_timezone(&Utc) - Utc::now();
+    println!(
+        "{} on {}, in {}h {:02}m",
+        prayer,
+        prayer.get_datetime().format("%A, %d %B"),
+        left.num_hours(),
+        left.num_minutes() % 60
+    );
+}
+
+/// Finds the first prayer from the date on that has not yet begun
+fn next_prayer(timetable: &Timetable, from: NaiveDate) -> Option<Prayer> {
+    timetable
+        .iter()
+        .filter(|day| day.get_date() >= from)
+        .flat_map(|day| day.get_prayers())
+        .find(|prayer| !get_performed_status(prayer.get_datetime()))
+}
+
+fn exit_code(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::Location | ErrorKind::Settings => 2,
+        ErrorKind::Network | ErrorKind::Http => 3,
+        ErrorKind::Parse | ErrorKind::Range => 4,
+        ErrorKind::Calculation => 5,
+        ErrorKind::Cache => 6,
+        ErrorKind::Io => 7,
+    }
+}
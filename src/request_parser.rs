@@ -1,35 +1,122 @@
+//! Readers of CSV timetables, from the website or a local file
+//!
+//! The readers work on bytes from anywhere and need neither the network nor an async runtime.
+
 use crate::{
-    core::{get_performed_status, prayer::Prayer},
-    time::{day::Day, month::Month},
-    types::{PrayerName, UmmahError, UmmahResult},
+    argparser::settings::{month_range, PrayerSettings},
+    core::{get_performed_status, prayer::Prayer, timing::Timing},
+    time::{day::Day, month::Month, zone},
+    types::{PrayerName, TimingName, UmmahError, UmmahResult},
 };
 
-use chrono::{Datelike, Duration, Local, NaiveDate, NaiveTime};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use chrono_tz::Tz;
 use serde::Deserialize;
 
+use std::ops::RangeInclusive;
+
 const MAX_DAYS: usize = 32;
 
 static DATE_FMT: &str = "%a %d %b %Y";
+static DAY_MONTH_FMT: &str = "%d %b %Y";
+static ISO_DATE_FMT: &str = "%Y-%m-%d";
 static TIME_FMT: &str = "%k:%M";
 
-pub fn parse_csv_file(data: bytes::Bytes) -> UmmahResult<Month> {
-    let mut csv_reader = csv::Reader::from_reader(data.as_ref());
+/// Timetable columns in the order their times must follow
+static COLUMNS: [&str; 6] = ["Fajr", "Sunrise", "Dhuhr", "Asr", "Maghrib", "Isha"];
+static DAY_COLUMN: &str = "Day";
+const ISHA_COLUMN: usize = 5;
+
+/// Reads a month of the website's timetable, as [get_prayer_times](crate::core::get_prayer_times) would give it
+///
+/// The user's offsets and Hijri calendar in the settings are applied to the times.
+pub fn parse_month(
+    prayer_settings: &PrayerSettings,
+    data: &[u8],
+    date: NaiveDate,
+) -> UmmahResult<Month> {
+    let mut month = parse_csv_file(data, prayer_settings.time_zone(), &month_range(date))?;
+    month.apply_settings(prayer_settings);
+    Ok(month)
+}
+
+/// Reads the website's timetable for the requested range of dates
+///
+/// The website leaves the year out of its dates, so it is taken from the range.
+/// The times are as written, without the user's offsets.
+pub fn parse_csv_file(
+    data: &[u8],
+    time_zone: Option<Tz>,
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Month> {
+    parse_records(data, time_zone, |row, day| {
+        parse_prayer_date(row, day, range).map(Some)
+    })
+}
+
+/// Reads a local timetable, such as a mosque's, keeping only the days in the range
+///
+/// Dates are written as 2027-01-31, or as on the website with the year taken from the range.
+pub fn parse_local_csv_file(
+    data: &[u8],
+    time_zone: Option<Tz>,
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<Month> {
+    parse_records(data, time_zone, |row, day| {
+        let date = match NaiveDate::parse_from_str(day.trim(), ISO_DATE_FMT) {
+            Ok(date) => date,
+            Err(_) => match parse_prayer_date(row, day, range) {
+                Ok(date) => date,
+                Err(UmmahError::DateRange { .. }) => return Ok(None),
+                Err(e) => return Err(e),
+            },
+        };
+
+        Ok(range.contains(&date).then(|| date))
+    })
+}
+
+/// Reads each record whose date is kept by `parse_date`, which is given the row and its day
+fn parse_records<F: Fn(usize, &str) -> UmmahResult<Option<NaiveDate>>>(
+    data: &[u8],
+    time_zone: Option<Tz>,
+    parse_date: F,
+) -> UmmahResult<Month> {
+    let mut csv_reader = csv::Reader::from_reader(data);
+    let headers = csv_reader.headers().ok().cloned();
     let mut days = Vec::with_capacity(MAX_DAYS);
-    for record in csv_reader.records() {
-        let day = record
+    for (index, record) in csv_reader.records().enumerate() {
+        let row = index + 1;
+        let prayer = record
             .and_then(|x| x.deserialize::<'_, CSVPrayer>(None))
-            .map_err(UmmahError::CSV)?
-            .build()?;
-        days.push(day);
+            .map_err(|source| UmmahError::Csv {
+                row,
+                column: error_column(&source, headers.as_ref()),
+                source,
+            })?;
+        if let Some(date) = parse_date(row, &prayer.day)? {
+            days.push(prayer.build(row, date, time_zone)?);
+        }
     }
     Ok(Month::new(days))
 }
 
+/// Names the column a CSV error occurred in, if it occurred in one
+fn error_column(error: &csv::Error, headers: Option<&csv::StringRecord>) -> Option<String> {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err
+            .field()
+            .and_then(|field| headers?.get(field as usize))
+            .map(ToOwned::to_owned),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CSVPrayer {
     day: String,
     fajr: String,
-    _s: String,
+    sunrise: String,
     dhuhr: String,
     asr: String,
     maghrib: String,
@@ -37,48 +124,427 @@ pub struct CSVPrayer {
 }
 
 impl CSVPrayer {
-    pub fn build(self) -> UmmahResult<Day> {
-        let rhs = Duration::hours(12);
-
-        let date = parse_prayer_date(&self.day)?;
-
-        let fajr = parse_prayer_time(&self.fajr, None)?;
-        let dhuhr = parse_prayer_time(&self.dhuhr, Some(rhs))?;
-        let asr = parse_prayer_time(&self.asr, Some(rhs))?;
-        let maghrib = parse_prayer_time(&self.maghrib, Some(rhs))?;
-        let isha = parse_prayer_time(&self.isha, Some(rhs))?;
-
-        let day = Day::new(
+    /// Builds the day from the given row, reading the times on the clock of the given zone
+    pub fn build(self, row: usize, date: NaiveDate, time_zone: Option<Tz>) -> UmmahResult<Day> {
+        build_day(
+            row,
             date,
             [
-                Prayer::new(PrayerName::Fajr, fajr, get_performed_status(date, fajr)),
-                Prayer::new(PrayerName::Dhuhr, dhuhr, get_performed_status(date, dhuhr)),
-                Prayer::new(PrayerName::Asr, asr, get_performed_status(date, asr)),
-                Prayer::new(
-                    PrayerName::Maghrib,
-                    maghrib,
-                    get_performed_status(date, maghrib),
-                ),
-                Prayer::new(PrayerName::Isha, isha, get_performed_status(date, isha)),
+                &self.fajr,
+                &self.sunrise,
+                &self.dhuhr,
+                &self.asr,
+                &self.maghrib,
+                &self.isha,
             ],
+            time_zone,
+        )
+    }
+}
+
+/// Builds a day from the times of Fajr, sunrise, Dhuhr, Asr, Maghrib and Isha as written in a timetable
+///
+/// Sunset is taken to be at Maghrib, as it is for every method the website offers.
+pub(crate) fn build_day(
+    row: usize,
+    date: NaiveDate,
+    values: [&str; 6],
+    time_zone: Option<Tz>,
+) -> UmmahResult<Day> {
+    let [fajr, sunrise, dhuhr, asr, maghrib, isha] =
+        parse_prayer_times(row, date, values)?.map(|time| zone::from_local(time_zone, time));
+
+    let day = Day::new(
+        date,
+        [
+            Prayer::new(PrayerName::Fajr, fajr, get_performed_status(fajr)),
+            Prayer::new(PrayerName::Dhuhr, dhuhr, get_performed_status(dhuhr)),
+            Prayer::new(PrayerName::Asr, asr, get_performed_status(asr)),
+            Prayer::new(PrayerName::Maghrib, maghrib, get_performed_status(maghrib)),
+            Prayer::new(PrayerName::Isha, isha, get_performed_status(isha)),
+        ],
+    )
+    .with_timings(vec![
+        Timing::new(TimingName::Sunrise, sunrise),
+        Timing::new(TimingName::Sunset, maghrib),
+    ]);
+
+    Ok(day)
+}
+
+/// Finds the year in which the date falls inside the range, checking the weekday against it
+fn parse_prayer_date(
+    row: usize,
+    prayer_date: &str,
+    range: &RangeInclusive<NaiveDate>,
+) -> UmmahResult<NaiveDate> {
+    let invalid = |error: chrono::ParseError| UmmahError::Timetable {
+        row,
+        column: DAY_COLUMN,
+        value: prayer_date.to_owned(),
+        reason: error.to_string(),
+    };
+
+    let day_month = prayer_date
+        .split_once(' ')
+        .map_or(prayer_date, |(_, day_month)| day_month);
+    let parse_in = |year: i32| {
+        NaiveDate::parse_from_str(&format!("{} {}", day_month, year), DAY_MONTH_FMT)
+            .map_err(invalid)
+    };
+
+    let mut date = None;
+    for year in range.start().year()..=range.end().year() {
+        let candidate = parse_in(year)?;
+        if range.contains(&candidate) {
+            date = Some(candidate);
+            break;
+        }
+    }
+
+    match date {
+        Some(date) => {
+            NaiveDate::parse_from_str(&format!("{} {}", prayer_date, date.year()), DATE_FMT)
+                .map_err(invalid)
+        }
+        None => Err(UmmahError::DateRange {
+            row,
+            date: prayer_date.to_owned(),
+            start: *range.start(),
+            end: *range.end(),
+        }),
+    }
+}
+
+/// How a time is written in the timetable
+#[derive(Debug, Clone, Copy)]
+enum Clock {
+    /// A 12-hour time with an AM or PM marker, already on the 24-hour clock
+    Marked(NaiveTime),
+    /// An hour that only appears on the 24-hour clock
+    TwentyFourHour(NaiveTime),
+    /// An hour from 1 to 12 without a marker, which could be on either clock
+    Unmarked(NaiveTime),
+}
+
+impl Clock {
+    /// Readings of the time on the 24-hour clock, earliest first
+    fn readings(self, is_24_hour: bool) -> Vec<NaiveTime> {
+        match self {
+            Clock::Marked(time) | Clock::TwentyFourHour(time) => vec![time],
+            Clock::Unmarked(time) if is_24_hour => vec![time],
+            Clock::Unmarked(time) => {
+                let morning = time.with_hour(time.hour() % 12).unwrap_or(time);
+                vec![morning, morning + Duration::hours(12)]
+            }
+        }
+    }
+}
+
+/// Reads the times of a row, each placed on the first reading after the one before it
+///
+/// A row with any hour only found on the 24-hour clock is read as 24-hour throughout;
+/// otherwise unmarked times are taken as morning or afternoon to keep the row in order.
+/// Only Isha may pass midnight, and then only until the following Fajr.
+fn parse_prayer_times(
+    row: usize,
+    date: NaiveDate,
+    values: [&str; 6],
+) -> UmmahResult<[NaiveDateTime; 6]> {
+    let invalid = |column: usize, reason: String| UmmahError::Timetable {
+        row,
+        column: COLUMNS[column],
+        value: values[column].to_owned(),
+        reason,
+    };
+
+    let mut clocks = Vec::with_capacity(COLUMNS.len());
+    for (column, value) in values.iter().enumerate() {
+        clocks.push(
+            parse_prayer_time(value)
+                .ok_or_else(|| invalid(column, "not a 12 or 24-hour time".to_owned()))?,
         );
+    }
+
+    let is_24_hour = clocks
+        .iter()
+        .any(|clock| matches!(clock, Clock::TwentyFourHour(_)));
 
-        Ok(day)
+    let mut times = [date.and_hms(0, 0, 0); 6];
+    for (column, clock) in clocks.into_iter().enumerate() {
+        let dates = if column == ISHA_COLUMN {
+            vec![date, date.succ()]
+        } else {
+            vec![date]
+        };
+
+        let mut readings = dates.into_iter().flat_map(|date| {
+            clock
+                .readings(is_24_hour)
+                .into_iter()
+                .map(move |time| date.and_time(time))
+        });
+
+        times[column] = match column.checked_sub(1) {
+            None => readings.next(),
+            Some(previous) => readings.find(|time| *time > times[previous]),
+        }
+        .ok_or_else(|| invalid(column, format!("not after {}", COLUMNS[column - 1])))?;
     }
+
+    if times[ISHA_COLUMN] >= times[0] + Duration::days(1) {
+        return Err(invalid(
+            ISHA_COLUMN,
+            format!("not before the next {}", COLUMNS[0]),
+        ));
+    }
+
+    Ok(times)
 }
 
-fn parse_prayer_date(prayer_date: &str) -> UmmahResult<NaiveDate> {
-    let prayer_date = format!("{} {}", prayer_date, Local::now().year());
-    NaiveDate::parse_from_str(&prayer_date, DATE_FMT).map_err(UmmahError::DateTime)
+/// Reads a time with or without an AM or PM marker
+fn parse_prayer_time(prayer_time: &str) -> Option<Clock> {
+    let normalised = prayer_time
+        .to_ascii_lowercase()
+        .replace(|c: char| c == '.' || c.is_whitespace(), "");
+
+    let (digits, is_pm) = match normalised.strip_suffix("am") {
+        Some(digits) => (digits, Some(false)),
+        None => match normalised.strip_suffix("pm") {
+            Some(digits) => (digits, Some(true)),
+            None => (normalised.as_str(), None),
+        },
+    };
+
+    let time = NaiveTime::parse_from_str(digits, TIME_FMT).ok()?;
+
+    match (is_pm, time.hour()) {
+        (Some(_), 0) | (Some(_), 13..=23) => None,
+        (Some(is_pm), hour) => time
+            .with_hour(hour % 12 + if is_pm { 12 } else { 0 })
+            .map(Clock::Marked),
+        (None, 1..=12) => Some(Clock::Unmarked(time)),
+        (None, _) => Some(Clock::TwentyFourHour(time)),
+    }
 }
 
-fn parse_prayer_time(prayer_time: &str, with_retime: Option<Duration>) -> UmmahResult<NaiveTime> {
-    let mut time =
-        NaiveTime::parse_from_str(prayer_time, TIME_FMT).map_err(UmmahError::DateTime)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono_tz::Europe::London;
 
-    if let Some(retime) = with_retime {
-        time = time.overflowing_add_signed(retime).0;
+    static HEADER: &str = "day,fajr,sunrise,dhuhr,asr,maghrib,isha\n";
+
+    fn october() -> RangeInclusive<NaiveDate> {
+        month_range(NaiveDate::from_ymd(2026, 10, 1))
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn reads_each_way_of_writing_a_time() {
+        let cases = [
+            ("5:10", Some("05:10"), false),
+            (" 5:10 ", Some("05:10"), false),
+            ("17:30", Some("17:30"), true),
+            ("0:30", Some("00:30"), true),
+            ("5:10 PM", Some("17:10"), true),
+            ("5:10pm", Some("17:10"), true),
+            ("5:10 p.m.", Some("17:10"), true),
+            ("12:15 am", Some("00:15"), true),
+            ("12:15 pm", Some("12:15"), true),
+            ("13:00 pm", None, false),
+            ("0:30 am", None, false),
+            ("five", None, false),
+            ("", None, false),
+        ];
+
+        for (value, expected, is_fixed) in cases {
+            let clock = parse_prayer_time(value);
+            let readings = clock.map(|clock| clock.readings(false));
+
+            match expected {
+                Some(expected) => {
+                    let readings = readings.unwrap_or_else(|| panic!("{:?} not read", value));
+                    assert_eq!(readings[0], time(expected), "{:?}", value);
+                    assert_eq!(readings.len() == 1, is_fixed, "{:?}", value);
+                }
+                None => assert!(clock.is_none(), "{:?} read as {:?}", value, clock),
+            }
+        }
     }
 
-    Ok(time)
+    #[test]
+    fn reads_rows_on_either_clock() {
+        let date = NaiveDate::from_ymd(2026, 6, 21);
+        let cases = [
+            (
+                "unmarked 12-hour",
+                ["5:10", "7:11", "12:50", "3:39", "6:20", "7:50"],
+                ["05:10", "07:11", "12:50", "15:39", "18:20", "19:50"],
+                false,
+            ),
+            (
+                "24-hour",
+                ["05:10", "07:11", "12:50", "15:39", "18:20", "19:50"],
+                ["05:10", "07:11", "12:50", "15:39", "18:20", "19:50"],
+                false,
+            ),
+            (
+                "marked 12-hour",
+                [
+                    "5:10 am", "7:11 am", "12:50 pm", "3:39 pm", "6:20 pm", "7:50 pm",
+                ],
+                ["05:10", "07:11", "12:50", "15:39", "18:20", "19:50"],
+                false,
+            ),
+            (
+                "24-hour with Isha after midnight",
+                ["1:30", "4:40", "13:00", "17:20", "21:20", "0:40"],
+                ["01:30", "04:40", "13:00", "17:20", "21:20", "00:40"],
+                true,
+            ),
+            (
+                "12-hour with Isha after midnight",
+                ["2:50", "4:45", "1:05", "5:25", "9:20", "12:10"],
+                ["02:50", "04:45", "13:05", "17:25", "21:20", "00:10"],
+                true,
+            ),
+            (
+                "12-hour with Isha before midnight",
+                ["2:50", "4:45", "1:05", "5:25", "9:20", "11:10"],
+                ["02:50", "04:45", "13:05", "17:25", "21:20", "23:10"],
+                false,
+            ),
+        ];
+
+        for (case, values, expected, is_isha_tomorrow) in cases {
+            let times =
+                parse_prayer_times(1, date, values).unwrap_or_else(|e| panic!("{}: {}", case, e));
+
+            for (column, (time, expected)) in times.iter().zip(expected).enumerate() {
+                let expected_date = if column == ISHA_COLUMN && is_isha_tomorrow {
+                    date.succ()
+                } else {
+                    date
+                };
+                assert_eq!(
+                    *time,
+                    expected_date.and_time(self::time(expected)),
+                    "{}: {}",
+                    case,
+                    COLUMNS[column]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names_the_row_column_and_value_of_invalid_times() {
+        let date = NaiveDate::from_ymd(2026, 6, 21);
+        let cases = [
+            (
+                ["5:xx", "7:11", "12:50", "3:39", "6:20", "7:50"],
+                "Fajr",
+                "5:xx",
+            ),
+            (
+                ["05:10", "07:11", "13:50", "12:39", "18:20", "19:50"],
+                "Asr",
+                "12:39",
+            ),
+            (
+                ["01:30", "04:40", "13:00", "17:20", "21:20", "02:00"],
+                "Isha",
+                "02:00",
+            ),
+        ];
+
+        for (values, expected_column, expected_value) in cases {
+            match parse_prayer_times(7, date, values) {
+                Err(UmmahError::Timetable {
+                    row, column, value, ..
+                }) => {
+                    assert_eq!(row, 7);
+                    assert_eq!(column, expected_column, "{:?}", values);
+                    assert_eq!(value, expected_value, "{:?}", values);
+                }
+                result => panic!("{:?} gave {:?}", values, result.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_a_timetable() {
+        let data = format!(
+            "{}Thu 01 Oct,5:10,7:11,12:50,3:39,6:20,7:50\nFri 02 Oct,5:11,7:12,12:50,3:38,6:19,7:49\n",
+            HEADER
+        );
+
+        let month = parse_csv_file(data.as_bytes(), Some(London), &october()).unwrap();
+
+        assert_eq!(month.len(), 2);
+        let day = month.iter().last().unwrap();
+        assert_eq!(day.get_date(), NaiveDate::from_ymd(2026, 10, 2));
+        assert_eq!(day.get_prayers()[2].get_time(), time("15:38"));
+        assert_eq!(day.get_prayers()[3].get_time(), time("18:19"));
+    }
+
+    #[test]
+    fn names_the_row_of_invalid_records() {
+        let first = "Thu 01 Oct,5:10,7:11,12:50,3:39,6:20,7:50\n";
+        let cases = [
+            (
+                format!(
+                    "{}{}Fri 02 Oct,5:11,7:12,12:50,3:38,6:19,late\n",
+                    HEADER, first
+                ),
+                2,
+            ),
+            (
+                format!(
+                    "{}{}Fri 02 Nov,5:11,7:12,12:50,3:38,6:19,7:49\n",
+                    HEADER, first
+                ),
+                2,
+            ),
+            (
+                format!("{}{}Fri 02 Oct,5:11,7:12,12:50\n", HEADER, first),
+                2,
+            ),
+            (
+                format!("{}Wed 01 Oct,5:10,7:11,12:50,3:39,6:20,7:50\n", HEADER),
+                1,
+            ),
+        ];
+
+        for (data, expected_row) in cases {
+            let row = match parse_csv_file(data.as_bytes(), Some(London), &october()) {
+                Err(UmmahError::Timetable { row, .. })
+                | Err(UmmahError::DateRange { row, .. })
+                | Err(UmmahError::Csv { row, .. }) => row,
+                result => panic!("{:?} gave {:?}", data, result.map(|_| ())),
+            };
+            assert_eq!(row, expected_row, "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn skips_local_dates_outside_the_range() {
+        let data = format!(
+            "{}2026-09-30,5:08,7:09,12:51,3:41,6:22,7:52\n2026-10-01,5:10,7:11,12:50,3:39,6:20,7:50\n",
+            HEADER
+        );
+
+        let month = parse_local_csv_file(data.as_bytes(), Some(London), &october()).unwrap();
+
+        assert_eq!(month.len(), 1);
+        assert_eq!(
+            month.iter().next().unwrap().get_date(),
+            NaiveDate::from_ymd(2026, 10, 1)
+        );
+    }
 }
@@ -0,0 +1,152 @@
+//! Providers of timetables, from the website to local calculation
+//!
+//! Every provider implements [TimetableSource], and the settings hold a chain of
+//! [Sources](Source). Those that support the location and methods are tried in order,
+//! each used only when the ones before it fail, so calculation at the end of the chain
+//! keeps the times available when the website is down.
+
+pub mod calculation;
+#[cfg(feature = "network")]
+pub mod json_api;
+pub mod mosque_csv;
+#[cfg(feature = "network")]
+pub mod salahtimes;
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    time::timetable::Timetable,
+    types::{Origin, UmmahResult},
+};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, future::Future, ops::RangeInclusive, path::PathBuf, pin::Pin, str::FromStr};
+
+use self::{calculation::CalculationSource, mosque_csv::MosqueCsvSource};
+#[cfg(feature = "network")]
+use self::{json_api::JsonApiSource, salahtimes::SalahtimesSource};
+
+/// Timetable being fetched by a [TimetableSource]
+pub type SourceFuture<'a> = Pin<Box<dyn Future<Output = UmmahResult<Timetable>> + Send + 'a>>;
+
+/// A provider of prayer times
+///
+/// Sources give the times as published or calculated, before the user's offsets
+/// and Hijri calendar are applied.
+pub trait TimetableSource: Send + Sync {
+    /// Name and version of the provider, stored with cached timetables
+    fn provider(&self) -> String;
+
+    /// Checks if the source can give times for the location and methods in the settings
+    fn supports(&self, prayer_settings: &PrayerSettings) -> bool;
+
+    /// How the source obtains its times, recorded with each day
+    fn origin(&self) -> Origin;
+
+    /// Checks if the times are worth keeping in the [cache](crate::core::cache)
+    ///
+    /// Cached sources are always asked for whole calendar months. Only downloaded
+    /// times may be cached, as the cache does not tell apart calculation settings.
+    fn is_cached(&self) -> bool {
+        false
+    }
+
+    /// Gets the times for the dates in the range, which are all in one calendar month
+    fn fetch<'a>(
+        &'a self,
+        prayer_settings: &'a PrayerSettings,
+        range: RangeInclusive<NaiveDate>,
+    ) -> SourceFuture<'a>;
+}
+
+/// A timetable source chosen in the settings
+///
+/// Written on the command line as `salahtimes`, `calculation`, `csv:<path>` or `json`,
+/// where the website and API can be given another address as `salahtimes:<url>` or `json:<url>`.
+/// The website and API need the `network` feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Downloaded from www.salahtimes.com, or a server answering in the same way
+    #[cfg(feature = "network")]
+    Salahtimes { url: String },
+    /// Calculated locally
+    Calculation,
+    /// Read from a CSV file, such as a mosque's timetable
+    MosqueCsv { path: PathBuf },
+    /// Downloaded from an API answering in the style of api.aladhan.com
+    #[cfg(feature = "network")]
+    JsonApi { url: String },
+}
+
+impl Source {
+    /// Creates the provider for the source
+    pub fn build(&self) -> Box<dyn TimetableSource> {
+        match self {
+            #[cfg(feature = "network")]
+            Source::Salahtimes { url } => Box::new(SalahtimesSource::new(url)),
+            Source::Calculation => Box::new(CalculationSource),
+            Source::MosqueCsv { path } => Box::new(MosqueCsvSource::new(path)),
+            #[cfg(feature = "network")]
+            Source::JsonApi { url } => Box::new(JsonApiSource::new(url)),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "network")]
+            Source::Salahtimes { url } => write!(f, "salahtimes:{}", url),
+            Source::Calculation => write!(f, "calculation"),
+            Source::MosqueCsv { path } => write!(f, "csv:{}", path.display()),
+            #[cfg(feature = "network")]
+            Source::JsonApi { url } => write!(f, "json:{}", url),
+        }
+    }
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (s, None),
+        };
+
+        match (kind, value) {
+            #[cfg(feature = "network")]
+            ("salahtimes", url) => Ok(Source::Salahtimes {
+                url: url.unwrap_or(salahtimes::LINK).to_owned(),
+            }),
+            ("calculation", None) => Ok(Source::Calculation),
+            ("csv", Some(path)) => Ok(Source::MosqueCsv { path: path.into() }),
+            #[cfg(feature = "network")]
+            ("json", url) => Ok(Source::JsonApi {
+                url: url.unwrap_or(json_api::LINK).to_owned(),
+            }),
+            _ if cfg!(feature = "network") => Err(format!(
+                "Unknown source {:?}, expected salahtimes[:<url>], calculation, csv:<path> or json[:<url>]",
+                s
+            )),
+            _ => Err(format!(
+                "Unknown source {:?}, expected calculation or csv:<path>",
+                s
+            )),
+        }
+    }
+}
+
+/// Sources for settings cached without any: the website, then calculation for
+/// locations and methods it does not offer or when it cannot be reached
+pub(crate) fn default_sources() -> Vec<Source> {
+    vec![
+        #[cfg(feature = "network")]
+        Source::Salahtimes {
+            url: salahtimes::LINK.to_owned(),
+        },
+        Source::Calculation,
+    ]
+}
@@ -0,0 +1,36 @@
+//! Timetables calculated locally, without network access
+
+use crate::{argparser::settings::PrayerSettings, calculator::calculate_days, types::Origin};
+
+use chrono::NaiveDate;
+
+use std::ops::RangeInclusive;
+
+use super::{SourceFuture, TimetableSource};
+
+/// Calculates the times from the coordinates of the location, see [crate::calculator]
+#[derive(Debug, Clone, Copy)]
+pub struct CalculationSource;
+
+impl TimetableSource for CalculationSource {
+    fn provider(&self) -> String {
+        format!("calculator {}", env!("CARGO_PKG_VERSION"))
+    }
+
+    fn supports(&self, prayer_settings: &PrayerSettings) -> bool {
+        prayer_settings.location().coordinates.is_some()
+            && prayer_settings.methods().parameters().is_ok()
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::Calculated
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        prayer_settings: &'a PrayerSettings,
+        range: RangeInclusive<NaiveDate>,
+    ) -> SourceFuture<'a> {
+        Box::pin(async move { calculate_days(prayer_settings, &range) })
+    }
+}
@@ -0,0 +1,288 @@
+//! Timetables downloaded from a JSON API in the style of api.aladhan.com
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    core::{
+        request_handler::{Content, HttpClient},
+        timing::Timing,
+    },
+    request_parser::build_day,
+    time::{timetable::Timetable, zone},
+    types::{Coordinates, Origin, TimingName, UmmahError, UmmahResult},
+};
+
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+use std::ops::RangeInclusive;
+
+use super::{SourceFuture, TimetableSource};
+
+pub(crate) static LINK: &str = "https://api.aladhan.com/v1/calendar";
+
+static DATE_FMT: &str = "%d-%m-%Y";
+static TIME_FMT: &str = "%H:%M";
+
+/// Downloads a month of times for the coordinates of the location
+///
+/// The API is asked for `<url>?latitude=..&longitude=..&method=..&school=..&latitudeAdjustmentMethod=..&month=..&year=..`
+/// and answers with the times of each day under `data`, as api.aladhan.com does. Only the
+/// prayer methods and high latitude rules the API knows are supported.
+#[derive(Debug, Clone)]
+pub struct JsonApiSource {
+    url: String,
+}
+
+impl JsonApiSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+
+    fn query(
+        &self,
+        prayer_settings: &PrayerSettings,
+        coordinates: Coordinates,
+        date: NaiveDate,
+    ) -> String {
+        let methods = prayer_settings.methods();
+        format!(
+            "{}?latitude={}&longitude={}&method={}&school={}&latitudeAdjustmentMethod={}&month={}&year={}",
+            self.url,
+            coordinates.latitude,
+            coordinates.longitude,
+            methods.prayer.aladhan_id().unwrap_or_default(),
+            methods.asr.aladhan_id(),
+            methods.latitude.aladhan_id().unwrap_or_default(),
+            date.month(),
+            date.year()
+        )
+    }
+}
+
+impl TimetableSource for JsonApiSource {
+    fn provider(&self) -> String {
+        format!("{} {}", self.url, env!("CARGO_PKG_VERSION"))
+    }
+
+    fn supports(&self, prayer_settings: &PrayerSettings) -> bool {
+        prayer_settings.location().coordinates.is_some()
+            && prayer_settings.methods().prayer.aladhan_id().is_some()
+            && prayer_settings.methods().latitude.aladhan_id().is_some()
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::Downloaded
+    }
+
+    fn is_cached(&self) -> bool {
+        true
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        prayer_settings: &'a PrayerSettings,
+        range: RangeInclusive<NaiveDate>,
+    ) -> SourceFuture<'a> {
+        Box::pin(async move {
+            let coordinates = prayer_settings
+                .location()
+                .coordinates
+                .ok_or(UmmahError::Coordinates)?;
+            let query = self.query(prayer_settings, coordinates, *range.start());
+
+            let client = HttpClient::new(prayer_settings.proxy())?;
+            let data = client
+                .download(&query, Content::Json, "Downloading times")
+                .await?;
+
+            let mut timetable = parse_response(&query, &data, prayer_settings.time_zone())?;
+            timetable.retain_range(&range);
+            Ok(timetable)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarResponse {
+    data: Vec<CalendarDay>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarDay {
+    timings: CalendarTimings,
+    date: CalendarDate,
+    #[serde(default)]
+    meta: Option<CalendarMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CalendarTimings {
+    fajr: String,
+    sunrise: String,
+    dhuhr: String,
+    asr: String,
+    sunset: String,
+    maghrib: String,
+    isha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarDate {
+    gregorian: GregorianDate,
+}
+
+#[derive(Debug, Deserialize)]
+struct GregorianDate {
+    date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarMeta {
+    timezone: Option<String>,
+}
+
+/// Reads the days of a response from the URL, on the clock of the given zone or else the one the API names
+fn parse_response(url: &str, data: &[u8], time_zone: Option<Tz>) -> UmmahResult<Timetable> {
+    let response: CalendarResponse =
+        serde_json::from_slice(data).map_err(|source| UmmahError::Json {
+            url: url.to_owned(),
+            source,
+        })?;
+
+    let mut days = Vec::with_capacity(response.data.len());
+    for (index, day) in response.data.into_iter().enumerate() {
+        let row = index + 1;
+        let time_zone = time_zone.or_else(|| {
+            day.meta
+                .as_ref()
+                .and_then(|meta| meta.timezone.as_deref())
+                .and_then(|timezone| timezone.parse().ok())
+        });
+        let date =
+            NaiveDate::parse_from_str(&day.date.gregorian.date, DATE_FMT).map_err(|error| {
+                UmmahError::Timetable {
+                    row,
+                    column: "Date",
+                    value: day.date.gregorian.date.clone(),
+                    reason: error.to_string(),
+                }
+            })?;
+
+        let timings = &day.timings;
+        let mut built = build_day(
+            row,
+            date,
+            [
+                strip_zone(&timings.fajr),
+                strip_zone(&timings.sunrise),
+                strip_zone(&timings.dhuhr),
+                strip_zone(&timings.asr),
+                strip_zone(&timings.maghrib),
+                strip_zone(&timings.isha),
+            ],
+            time_zone,
+        )?;
+
+        let sunset =
+            NaiveTime::parse_from_str(strip_zone(&timings.sunset), TIME_FMT).map_err(|_| {
+                UmmahError::Timetable {
+                    row,
+                    column: "Sunset",
+                    value: timings.sunset.clone(),
+                    reason: "not a 24-hour time".to_owned(),
+                }
+            })?;
+        built.set_timing(Timing::new(
+            TimingName::Sunset,
+            zone::from_local(time_zone, date.and_time(sunset)),
+        ));
+
+        days.push(built);
+    }
+
+    Ok(Timetable::new(days))
+}
+
+/// Removes the abbreviation of the time zone that follows each time, as in `05:14 (BST)`
+fn strip_zone(time: &str) -> &str {
+    time.split_whitespace().next().unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::PrayerName;
+
+    use chrono::{DateTime, FixedOffset};
+
+    /// Three days either side of the clocks going forward, in the format of api.aladhan.com
+    static RESPONSE: &[u8] = include_bytes!("../../tests/fixtures/aladhan/london-2026-03.json");
+
+    fn time(timetable: &Timetable, day: u32, name: PrayerName) -> DateTime<FixedOffset> {
+        timetable
+            .select_by_date(NaiveDate::from_ymd(2026, 3, day))
+            .unwrap()
+            .get_prayer(name)
+            .get_datetime()
+    }
+
+    #[test]
+    fn reads_times_on_the_clock_the_api_names() {
+        let timetable = parse_response(LINK, RESPONSE, None).unwrap();
+
+        assert_eq!(timetable.len(), 3);
+        assert_eq!(
+            time(&timetable, 28, PrayerName::Fajr).to_rfc3339(),
+            "2026-03-28T04:38:00+00:00"
+        );
+        assert_eq!(
+            time(&timetable, 29, PrayerName::Fajr).to_rfc3339(),
+            "2026-03-29T05:35:00+01:00"
+        );
+        assert_eq!(
+            time(&timetable, 30, PrayerName::Isha).to_rfc3339(),
+            "2026-03-30T20:52:00+01:00"
+        );
+
+        let day = timetable.iter().nth(1).unwrap();
+        let sunset = day.get_timing(TimingName::Sunset).unwrap();
+        assert_eq!(
+            sunset.get_datetime().to_rfc3339(),
+            "2026-03-29T19:29:00+01:00"
+        );
+        assert!(day.get_timing(TimingName::Midnight).is_none());
+    }
+
+    #[test]
+    fn reads_times_on_the_clock_of_the_location() {
+        let timetable = parse_response(LINK, RESPONSE, Some(chrono_tz::UTC)).unwrap();
+
+        assert_eq!(
+            time(&timetable, 29, PrayerName::Fajr).to_rfc3339(),
+            "2026-03-29T05:35:00+00:00"
+        );
+    }
+
+    #[test]
+    fn names_the_day_it_cannot_read() {
+        let response = String::from_utf8(RESPONSE.to_vec())
+            .unwrap()
+            .replace("\"29-03-2026\"", "\"2026-03-29\"");
+
+        match parse_response(LINK, response.as_bytes(), None) {
+            Err(UmmahError::Timetable { row, column, .. }) => {
+                assert_eq!((row, column), (2, "Date"));
+            }
+            result => panic!("Expected an unreadable date, got {:?}", result.map(|_| ())),
+        }
+        assert!(matches!(
+            parse_response(LINK, b"<html>Not found</html>", None),
+            Err(UmmahError::Json { .. })
+        ));
+    }
+}
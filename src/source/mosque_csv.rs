@@ -0,0 +1,66 @@
+//! Timetables read from a local CSV file, such as one published by a mosque
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    request_parser::parse_local_csv_file,
+    types::{Origin, UmmahError},
+};
+
+use chrono::NaiveDate;
+
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use super::{SourceFuture, TimetableSource};
+
+/// Reads a CSV file with the columns `day,fajr,sunrise,dhuhr,asr,maghrib,isha`
+///
+/// Days are written as 2027-01-31, or as `Sun 31 Jan` like the website.
+/// Times may be on the 12 or 24-hour clock, with or without AM and PM.
+#[derive(Debug, Clone)]
+pub struct MosqueCsvSource {
+    path: PathBuf,
+}
+
+impl MosqueCsvSource {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+}
+
+impl TimetableSource for MosqueCsvSource {
+    /// The file and when it was last changed, so an updated timetable is read again
+    fn provider(&self) -> String {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |modified| modified.as_secs());
+
+        format!("{} {}", self.path.display(), modified)
+    }
+
+    fn supports(&self, _: &PrayerSettings) -> bool {
+        self.path.is_file()
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::File
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        prayer_settings: &'a PrayerSettings,
+        range: RangeInclusive<NaiveDate>,
+    ) -> SourceFuture<'a> {
+        Box::pin(async move {
+            let data = std::fs::read(&self.path).map_err(UmmahError::file(&self.path))?;
+            parse_local_csv_file(&data, prayer_settings.time_zone(), &range)
+        })
+    }
+}
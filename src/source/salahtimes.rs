@@ -0,0 +1,111 @@
+//! Timetables downloaded from www.salahtimes.com
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    core::request_handler::{Content, HttpClient},
+    request_parser::parse_csv_file,
+    types::{Origin, UmmahError},
+};
+
+use chrono::NaiveDate;
+
+use std::ops::RangeInclusive;
+
+use super::{SourceFuture, TimetableSource};
+
+pub(crate) static LINK: &str = "https://www.salahtimes.com/";
+
+/// Downloads the website's CSV timetables, or those of a server answering in the same way
+#[derive(Debug, Clone)]
+pub struct SalahtimesSource {
+    url: String,
+}
+
+impl SalahtimesSource {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+}
+
+impl TimetableSource for SalahtimesSource {
+    fn provider(&self) -> String {
+        format!("{} {}", self.url, env!("CARGO_PKG_VERSION"))
+    }
+
+    fn supports(&self, prayer_settings: &PrayerSettings) -> bool {
+        prayer_settings.is_downloadable()
+    }
+
+    fn origin(&self) -> Origin {
+        Origin::Downloaded
+    }
+
+    fn is_cached(&self) -> bool {
+        true
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        prayer_settings: &'a PrayerSettings,
+        range: RangeInclusive<NaiveDate>,
+    ) -> SourceFuture<'a> {
+        Box::pin(async move {
+            let client = HttpClient::new(prayer_settings.proxy())?;
+            let data = client
+                .download(
+                    &prayer_settings.query(&self.url, &range),
+                    Content::Csv,
+                    "Downloading times",
+                )
+                .await
+                .map_err(|error| match error {
+                    // The website has no page for a misspelt city
+                    UmmahError::Status { status: 404, .. } => {
+                        prayer_settings.unknown_city().unwrap_or(error)
+                    }
+                    error => error,
+                })?;
+            parse_csv_file(&data, prayer_settings.time_zone(), &range)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::request_handler::tests::{serve, Reply};
+
+    #[test]
+    fn names_cities_the_website_does_not_know() {
+        let (url, _) = serve(vec![Reply::Status(404, "text/html", "Not found")]);
+        let settings: PrayerSettings = serde_yaml::from_str(
+            "methods: {latitude: OneSeventh, prayer: MWL, asr: Shafi}\n\
+             location: {country: uk, city: leeeds, coordinates: {latitude: 53.8, longitude: -1.55}}\n\
+             is_audio_downloaded: false",
+        )
+        .unwrap();
+        let settings = settings.resolve().unwrap();
+        let month = NaiveDate::from_ymd(2026, 10, 1)..=NaiveDate::from_ymd(2026, 10, 31);
+
+        let result = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(SalahtimesSource::new(&url).fetch(&settings, month));
+
+        match result {
+            Err(UmmahError::City { city, suggestions }) => {
+                assert_eq!(city, "leeeds");
+                assert!(
+                    suggestions.contains(&"Leeds, UK".to_owned()),
+                    "{:?}",
+                    suggestions
+                );
+            }
+            result => panic!("Expected an unknown city, got {:?}", result.map(|_| ())),
+        }
+    }
+}
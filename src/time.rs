@@ -1,4 +1,8 @@
 //! Module for storing time relevant data
 
 pub mod day;
+pub mod hijri;
 pub mod month;
+pub mod ramadan;
+pub mod timetable;
+pub(crate) mod zone;
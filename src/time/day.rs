@@ -1,8 +1,14 @@
 //! Module for holding a [Day] of [Prayers](super::prayer::Prayer)
 
-use crate::core::{get_performed_status, prayer::Prayer};
+use crate::{
+    core::{get_performed_status, prayer::Prayer, timing::Timing},
+    types::{HijriCalendar, LatitudeAdjustment, PrayerName, Provenance, TimingName},
+};
+
+use super::hijri::HijriDate;
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
 use serde::{
     de::{MapAccess, SeqAccess, Visitor},
     Deserialize, Serialize,
@@ -14,7 +20,11 @@ use std::{cmp::Ordering, fmt};
 #[serde(field_identifier, rename_all = "lowercase")]
 enum DayField {
     Date,
+    Hijri,
     Prayers,
+    Timings,
+    Adjustment,
+    Provenance,
 }
 
 struct DayVisitor;
@@ -33,10 +43,21 @@ impl<'de> Visitor<'de> for DayVisitor {
         let date = seq
             .next_element()?
             .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let hijri: Option<HijriDate> = seq.next_element()?;
         let prayers = seq
             .next_element()?
-            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
-        Ok(Day::new(date, prayers))
+            .ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
+        let timings = seq.next_element()?.unwrap_or_default();
+        let adjustment = seq.next_element()?.flatten();
+        let provenance = seq.next_element()?.flatten();
+        let mut day = Day::new(date, prayers)
+            .with_timings(timings)
+            .with_adjustment(adjustment)
+            .with_provenance(provenance);
+        if let Some(hijri) = hijri {
+            day.set_hijri_date(hijri);
+        }
+        Ok(day)
     }
 
     fn visit_map<V>(self, mut map: V) -> Result<Day, V::Error>
@@ -44,7 +65,11 @@ impl<'de> Visitor<'de> for DayVisitor {
         V: MapAccess<'de>,
     {
         let mut date = None;
+        let mut hijri = None;
         let mut prayers = None;
+        let mut timings = None;
+        let mut adjustment = None;
+        let mut provenance = None;
         while let Some(key) = map.next_key()? {
             match key {
                 DayField::Date => {
@@ -53,12 +78,36 @@ impl<'de> Visitor<'de> for DayVisitor {
                     }
                     date = Some(map.next_value()?);
                 }
+                DayField::Hijri => {
+                    if hijri.is_some() {
+                        return Err(serde::de::Error::duplicate_field("hijri"));
+                    }
+                    hijri = Some(map.next_value()?);
+                }
                 DayField::Prayers => {
                     if prayers.is_some() {
                         return Err(serde::de::Error::duplicate_field("prayers"));
                     }
                     prayers = Some(map.next_value()?);
                 }
+                DayField::Timings => {
+                    if timings.is_some() {
+                        return Err(serde::de::Error::duplicate_field("timings"));
+                    }
+                    timings = Some(map.next_value()?);
+                }
+                DayField::Adjustment => {
+                    if adjustment.is_some() {
+                        return Err(serde::de::Error::duplicate_field("adjustment"));
+                    }
+                    adjustment = Some(map.next_value()?);
+                }
+                DayField::Provenance => {
+                    if provenance.is_some() {
+                        return Err(serde::de::Error::duplicate_field("provenance"));
+                    }
+                    provenance = Some(map.next_value()?);
+                }
             }
         }
         let date = date.ok_or_else(|| serde::de::Error::missing_field("date"))?;
@@ -66,10 +115,17 @@ impl<'de> Visitor<'de> for DayVisitor {
             prayers.ok_or_else(|| serde::de::Error::missing_field("prayers"))?;
 
         for prayer in prayers.iter_mut() {
-            prayer.set_performed(get_performed_status(date, prayer.get_time()));
+            prayer.set_performed(get_performed_status(prayer.get_datetime()));
         }
 
-        Ok(Day::new(date, prayers))
+        let mut day = Day::new(date, prayers)
+            .with_timings(timings.unwrap_or_default())
+            .with_adjustment(adjustment.flatten())
+            .with_provenance(provenance.flatten());
+        if let Some(hijri) = hijri {
+            day.set_hijri_date(hijri);
+        }
+        Ok(day)
     }
 }
 
@@ -77,7 +133,14 @@ impl<'de> Visitor<'de> for DayVisitor {
 #[derive(Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct Day {
     date: NaiveDate,
+    hijri: HijriDate,
     prayers: [Prayer; 5],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    timings: Vec<Timing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    adjustment: Option<LatitudeAdjustment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provenance: Option<Provenance>,
 }
 
 impl<'de> Deserialize<'de> for Day {
@@ -85,13 +148,24 @@ impl<'de> Deserialize<'de> for Day {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_struct("Prayer", &["date", "prayers"], DayVisitor)
+        deserializer.deserialize_struct(
+            "Prayer",
+            &[
+                "date",
+                "hijri",
+                "prayers",
+                "timings",
+                "adjustment",
+                "provenance",
+            ],
+            DayVisitor,
+        )
     }
 }
 
 impl fmt::Display for Day {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut output = self.date.format("%A, %d %B %Y").to_string();
+        let mut output = format!("{} / {}", self.date.format("%A, %d %B %Y"), self.hijri);
 
         output = format!("\n{:^62}\n", output);
 
@@ -107,7 +181,11 @@ impl fmt::Display for Day {
         output += "|\n|";
 
         for (idx, prayer) in self.prayers.iter().enumerate() {
-            output += &format!("{:^10}", prayer.get_time().to_string());
+            let mut time = prayer.get_time().to_string();
+            if self.is_adjusted(prayer.get_name()) {
+                time += "*";
+            }
+            output += &format!("{:^10}", time);
             if idx < 4 {
                 output += " | ";
             }
@@ -115,6 +193,39 @@ impl fmt::Display for Day {
 
         output += &format!("|\n|{:=<62}|\n", "");
 
+        let mut line = String::new();
+        for timing in &self.timings {
+            let entry = format!(
+                "{}: {}",
+                timing.get_name(),
+                timing.get_time().format("%H:%M")
+            );
+            if !line.is_empty() && line.len() + entry.len() + 3 > 62 {
+                output += &format!("|{:^62}|\n", line);
+                line.clear();
+            }
+            if !line.is_empty() {
+                line += " | ";
+            }
+            line += &entry;
+        }
+
+        if !line.is_empty() {
+            output += &format!("|{:^62}|\n|{:=<62}|\n", line, "");
+        }
+
+        if let Some(adjustment) = &self.adjustment {
+            output += &format!("* {}\n", adjustment);
+        }
+
+        if let Some(provenance) = self
+            .provenance
+            .as_ref()
+            .filter(|provenance| provenance.fallback)
+        {
+            output += &format!("† {}\n", provenance);
+        }
+
         write!(f, "{output}")
     }
 }
@@ -149,12 +260,124 @@ impl Day {
         self.date
     }
 
+    /// Gets the date for the day in the Hijri calendar
+    pub fn hijri_date(&self) -> HijriDate {
+        self.hijri
+    }
+
     /// Gets all prayers for the day
     pub fn get_prayers(&self) -> [Prayer; 5] {
         self.prayers
     }
 
+    /// Gets the other times of the day, such as sunrise and midnight
+    pub fn get_timings(&self) -> &[Timing] {
+        &self.timings
+    }
+
+    /// Gets one of the other times of the day
+    pub fn get_timing(&self, name: TimingName) -> Option<&Timing> {
+        self.timings.iter().find(|timing| timing.get_name() == name)
+    }
+
+    /// Gets the prayers moved by a high latitude rule, if any
+    pub fn get_adjustment(&self) -> Option<&LatitudeAdjustment> {
+        self.adjustment.as_ref()
+    }
+
+    /// Gets where and when the day's times were obtained, if recorded
+    pub fn provenance(&self) -> Option<&Provenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Checks if the day's times came from a fallback source
+    pub fn is_fallback(&self) -> bool {
+        self.provenance
+            .as_ref()
+            .map_or(false, |provenance| provenance.fallback)
+    }
+
+    /// Shows the day's times on the clock of another time zone
+    ///
+    /// The date is kept, so times near midnight may belong to another date in that zone.
+    pub fn in_time_zone<Z: TimeZone>(&self, time_zone: &Z) -> Self {
+        Self {
+            prayers: self.prayers.map(|prayer| prayer.in_time_zone(time_zone)),
+            timings: self
+                .timings
+                .iter()
+                .map(|timing| timing.in_time_zone(time_zone))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Checks if a prayer was moved by a high latitude rule
+    pub fn is_adjusted(&self, name: PrayerName) -> bool {
+        self.adjustment
+            .as_ref()
+            .map_or(false, |adjustment| adjustment.prayers.contains(&name))
+    }
+
     pub(crate) fn new(date: NaiveDate, prayers: [Prayer; 5]) -> Self {
-        Self { date, prayers }
+        Self {
+            date,
+            hijri: HijriDate::from_gregorian(date, HijriCalendar::UmmAlQura, 0),
+            prayers,
+            timings: Vec::new(),
+            adjustment: None,
+            provenance: None,
+        }
+    }
+
+    pub(crate) fn with_timings(self, timings: Vec<Timing>) -> Self {
+        Self { timings, ..self }
+    }
+
+    /// Gets a prayer by name, relying on the prayers being stored in order
+    pub(crate) fn get_prayer(&self, name: PrayerName) -> &Prayer {
+        &self.prayers[name as usize]
+    }
+
+    /// Moves every prayer by the minutes given for its name
+    pub(crate) fn shift_prayers<F: Fn(PrayerName) -> i64>(
+        &mut self,
+        time_zone: Option<Tz>,
+        offset: F,
+    ) {
+        for prayer in self.prayers.iter_mut() {
+            prayer.shift(time_zone, offset(prayer.get_name()));
+            prayer.set_performed(get_performed_status(prayer.get_datetime()));
+        }
+    }
+
+    pub(crate) fn set_timing(&mut self, timing: Timing) {
+        match self
+            .timings
+            .iter_mut()
+            .find(|current| current.get_name() == timing.get_name())
+        {
+            Some(current) => *current = timing,
+            None => {
+                self.timings.push(timing);
+                self.timings.sort_by_key(Timing::get_name);
+            }
+        }
+    }
+
+    pub(crate) fn set_hijri_date(&mut self, hijri: HijriDate) {
+        self.hijri = hijri;
+    }
+
+    pub(crate) fn with_adjustment(self, adjustment: Option<LatitudeAdjustment>) -> Self {
+        Self { adjustment, ..self }
+    }
+
+    pub(crate) fn with_provenance(self, provenance: Option<Provenance>) -> Self {
+        Self { provenance, ..self }
+    }
+
+    pub(crate) fn set_provenance(&mut self, provenance: Provenance) {
+        self.provenance = Some(provenance);
     }
 }
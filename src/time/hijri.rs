@@ -0,0 +1,215 @@
+//! Module for converting dates to the [Hijri calendar](HijriDate)
+
+mod umm_al_qura;
+
+use crate::types::HijriCalendar;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// Julian day number of 1 Muharram 1 AH in the tabular calendar
+const TABULAR_EPOCH: i64 = 1948440;
+
+/// Julian day number offset from the days counted by [Datelike::num_days_from_ce]
+const CE_OFFSET: i64 = 1721425;
+
+/// Number of the month of Ramadan
+const RAMADAN: u32 = 9;
+
+const MONTH_NAMES: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi' al-Awwal",
+    "Rabi' al-Thani",
+    "Jumada al-Ula",
+    "Jumada al-Akhirah",
+    "Rajab",
+    "Sha'ban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qa'dah",
+    "Dhu al-Hijjah",
+];
+
+/// A date in the Hijri calendar
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HijriDate {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl fmt::Display for HijriDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {} AH", self.day, self.month_name(), self.year)
+    }
+}
+
+impl HijriDate {
+    /// Converts a Gregorian date, moved by the given number of days
+    ///
+    /// The adjustment allows for the start of the month being set by a local moon sighting.
+    /// Dates outside the Umm al-Qura table fall back to the tabular calendar.
+    pub fn from_gregorian(date: NaiveDate, calendar: HijriCalendar, adjustment: i64) -> Self {
+        let date = date + Duration::days(adjustment);
+
+        match calendar {
+            HijriCalendar::UmmAlQura => umm_al_qura(date).unwrap_or_else(|| tabular(date)),
+            HijriCalendar::Tabular => tabular(date),
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Gets the month, starting from 1 for Muharram
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn month_name(&self) -> &'static str {
+        MONTH_NAMES[self.month as usize - 1]
+    }
+
+    pub fn is_ramadan(&self) -> bool {
+        self.month == RAMADAN
+    }
+}
+
+/// Converts a date using the tabular calendar, as used by the Kuwaiti algorithm
+fn tabular(date: NaiveDate) -> HijriDate {
+    let julian_day = i64::from(date.num_days_from_ce()) + CE_OFFSET;
+
+    let days = julian_day - TABULAR_EPOCH + 10632;
+    let cycles = (days - 1) / 10631;
+    let days = days - 10631 * cycles + 354;
+    let years =
+        ((10985 - days) / 5316) * ((50 * days) / 17719) + (days / 5670) * ((43 * days) / 15238);
+    let days =
+        days - ((30 - years) / 15) * ((17719 * years) / 50) - (years / 16) * ((15238 * years) / 43)
+            + 29;
+    let month = (24 * days) / 709;
+
+    HijriDate {
+        year: (30 * cycles + years - 30) as i32,
+        month: month as u32,
+        day: (days - (709 * month) / 24) as u32,
+    }
+}
+
+/// Converts a date using the Umm al-Qura table, if the date is covered by it
+fn umm_al_qura(date: NaiveDate) -> Option<HijriDate> {
+    let (year, month, day) = umm_al_qura::FIRST_DAY;
+    let mut days = (date - NaiveDate::from_ymd(year, month, day)).num_days();
+    if days < 0 {
+        return None;
+    }
+
+    for (year, lengths) in (umm_al_qura::FIRST_YEAR..).zip(umm_al_qura::MONTH_LENGTHS) {
+        for month in 0..12 {
+            let length = if lengths & (1 << month) == 0 { 29 } else { 30 };
+            if days < length {
+                return Some(HijriDate {
+                    year,
+                    month: month + 1,
+                    day: days as u32 + 1,
+                });
+            }
+            days -= length;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    fn hijri(year: i32, month: u32, day: u32) -> HijriDate {
+        HijriDate { year, month, day }
+    }
+
+    #[test]
+    fn converts_dates_in_the_umm_al_qura_calendar() {
+        for (gregorian, expected) in [
+            (date(2024, 3, 10), hijri(1445, 8, 29)),
+            (date(2024, 3, 11), hijri(1445, 9, 1)),
+            (date(2024, 4, 9), hijri(1445, 9, 30)),
+            (date(2024, 4, 10), hijri(1445, 10, 1)),
+            (date(1882, 11, 12), hijri(1300, 1, 1)),
+        ] {
+            assert_eq!(
+                HijriDate::from_gregorian(gregorian, HijriCalendar::UmmAlQura, 0),
+                expected,
+                "{}",
+                gregorian
+            );
+        }
+
+        let ramadan = HijriDate::from_gregorian(date(2024, 3, 11), HijriCalendar::UmmAlQura, 0);
+        assert!(ramadan.is_ramadan());
+        assert_eq!(ramadan.to_string(), "1 Ramadan 1445 AH");
+    }
+
+    #[test]
+    fn converts_dates_in_the_tabular_calendar() {
+        for (gregorian, expected) in [
+            (date(622, 7, 19), hijri(1, 1, 1)),
+            (date(1979, 11, 21), hijri(1400, 1, 1)),
+            (date(2000, 1, 1), hijri(1420, 9, 24)),
+            (date(2024, 3, 11), hijri(1445, 9, 1)),
+        ] {
+            assert_eq!(
+                HijriDate::from_gregorian(gregorian, HijriCalendar::Tabular, 0),
+                expected,
+                "{}",
+                gregorian
+            );
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_tabular_calendar_outside_the_table() {
+        assert_eq!(
+            HijriDate::from_gregorian(date(1882, 11, 11), HijriCalendar::UmmAlQura, 0),
+            hijri(1299, 12, 29)
+        );
+    }
+
+    #[test]
+    fn moves_dates_by_the_adjustment() {
+        let ramadan = hijri(1445, 9, 1);
+
+        for (gregorian, adjustment) in [(date(2024, 3, 10), 1), (date(2024, 3, 12), -1)] {
+            assert_eq!(
+                HijriDate::from_gregorian(gregorian, HijriCalendar::UmmAlQura, adjustment),
+                ramadan
+            );
+        }
+        assert_eq!(
+            HijriDate::from_gregorian(date(2024, 3, 11), HijriCalendar::UmmAlQura, -1),
+            hijri(1445, 8, 29)
+        );
+    }
+
+    #[test]
+    fn keeps_umm_al_qura_years_the_length_of_a_lunar_year() {
+        for (year, lengths) in (umm_al_qura::FIRST_YEAR..).zip(umm_al_qura::MONTH_LENGTHS) {
+            // Twelve months of 29 days, and a day more for each month of 30
+            let days = 12 * 29 + lengths.count_ones();
+            assert!((354..=355).contains(&days), "{} AH has {} days", year, days);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Module for holding the [days of fasting](Fast) in [Ramadan]
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    types::{PrayerName, TimingName},
+};
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime};
+
+use std::{fmt, ops::RangeInclusive};
+
+use super::day::Day;
+
+/// Number of days searched for the start of the next Ramadan
+const SEARCH_DAYS: i64 = 400;
+
+/// A day of fasting, with the times at which the fast begins and ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fast {
+    day: Day,
+    suhoor_end: DateTime<FixedOffset>,
+}
+
+impl fmt::Display for Fast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let duration = self.duration();
+
+        write!(
+            f,
+            "{:>2} Ramadan | {} | Suhoor ends: {} | Iftar: {}",
+            self.number(),
+            self.day.get_date().format("%a %d %b"),
+            self.suhoor_end().format("%H:%M"),
+            self.iftar().format("%H:%M"),
+        )?;
+
+        if let Some(taraweeh) = self.taraweeh() {
+            write!(f, " | Taraweeh: {}", taraweeh.format("%H:%M"))?;
+        }
+
+        write!(
+            f,
+            " | Fast: {}h {:02}m",
+            duration.num_hours(),
+            duration.num_minutes() % 60
+        )
+    }
+}
+
+impl Fast {
+    /// Gets all the times for the day
+    pub fn get_day(&self) -> &Day {
+        &self.day
+    }
+
+    /// Gets the day of Ramadan, starting from 1
+    pub fn number(&self) -> u32 {
+        self.day.hijri_date().day()
+    }
+
+    /// Gets the time at which eating must stop
+    pub fn suhoor_end(&self) -> NaiveTime {
+        self.suhoor_end.time()
+    }
+
+    /// Gets the time at which the fast is broken, at Maghrib
+    pub fn iftar(&self) -> NaiveTime {
+        self.day.get_prayer(PrayerName::Maghrib).get_time()
+    }
+
+    /// Gets the start of Taraweeh, if the day has one
+    pub fn taraweeh(&self) -> Option<NaiveTime> {
+        self.day
+            .get_timing(TimingName::Taraweeh)
+            .map(|timing| timing.get_time())
+    }
+
+    /// Gets the time between the end of Suhoor and Iftar
+    pub fn duration(&self) -> Duration {
+        self.day.get_prayer(PrayerName::Maghrib).get_datetime() - self.suhoor_end
+    }
+
+    fn new(day: Day, suhoor_margin: Option<i64>) -> Self {
+        let fajr = day.get_prayer(PrayerName::Fajr).get_datetime();
+
+        let suhoor_end = match (suhoor_margin, day.get_timing(TimingName::Imsak)) {
+            (Some(margin), _) => fajr - Duration::minutes(margin),
+            (None, Some(imsak)) => imsak.get_datetime(),
+            (None, None) => fajr,
+        };
+
+        Self { day, suhoor_end }
+    }
+}
+
+/// Holds every [day of fasting](Fast) in Ramadan
+///
+/// Ramadan usually spans two Gregorian months, so the days may come from
+/// more than one [Month](super::month::Month).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ramadan {
+    year: i32,
+    fasts: Vec<Fast>,
+}
+
+impl fmt::Display for Ramadan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Ramadan {} AH", self.year)?;
+
+        for fast in &self.fasts {
+            writeln!(f, "{}", fast)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Ramadan {
+    /// Gets the Hijri year
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Fast> {
+        self.fasts.iter()
+    }
+
+    /// Collects the days of Ramadan in the given Hijri year, ignoring all others
+    pub(crate) fn new<'a, I: IntoIterator<Item = &'a Day>>(
+        year: i32,
+        days: I,
+        prayer_settings: &PrayerSettings,
+    ) -> Self {
+        let mut fasts = days
+            .into_iter()
+            .filter(|day| day.hijri_date().is_ramadan() && day.hijri_date().year() == year)
+            .map(|day| Fast::new(day.clone(), prayer_settings.suhoor_margin()))
+            .collect::<Vec<_>>();
+        fasts.sort_by_key(|fast| fast.day.get_date());
+        fasts.dedup_by_key(|fast| fast.day.get_date());
+
+        Self { year, fasts }
+    }
+}
+
+/// Finds the current Ramadan, or the next one if `date` is outside of Ramadan
+///
+/// Returns the Hijri year and the Gregorian dates of its first and last days.
+pub(crate) fn find_dates(
+    prayer_settings: &PrayerSettings,
+    date: NaiveDate,
+) -> Option<(i32, RangeInclusive<NaiveDate>)> {
+    let is_ramadan = |date: NaiveDate| prayer_settings.hijri_date(date).is_ramadan();
+
+    let mut first = (0..SEARCH_DAYS)
+        .map(|days| date + Duration::days(days))
+        .find(|date| is_ramadan(*date))?;
+    while is_ramadan(first.pred()) {
+        first = first.pred();
+    }
+
+    let mut last = first;
+    while is_ramadan(last.succ()) {
+        last = last.succ();
+    }
+
+    Some((prayer_settings.hijri_date(first).year(), first..=last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        argparser::settings::test_settings, calculator::calculate_timetable, types::Coordinates,
+    };
+
+    fn london() -> PrayerSettings {
+        test_settings(
+            Coordinates {
+                latitude: 51.5074,
+                longitude: -0.1278,
+            },
+            "Europe/London",
+            "{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}",
+        )
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn finds_the_current_or_next_ramadan() {
+        let settings = london();
+        let ramadan_1446 = Some((1446, date(2025, 3, 1)..=date(2025, 3, 29)));
+
+        assert_eq!(find_dates(&settings, date(2025, 1, 15)), ramadan_1446);
+        assert_eq!(find_dates(&settings, date(2025, 3, 1)), ramadan_1446);
+        assert_eq!(find_dates(&settings, date(2025, 3, 29)), ramadan_1446);
+        assert_eq!(
+            find_dates(&settings, date(2025, 3, 30)),
+            Some((1447, date(2026, 2, 18)..=date(2026, 3, 19)))
+        );
+    }
+
+    #[test]
+    fn sets_taraweeh_only_on_the_days_of_ramadan() {
+        let settings = london();
+        let timetable =
+            calculate_timetable(&settings, &(date(2025, 2, 26)..=date(2025, 4, 2))).unwrap();
+        let (year, dates) = find_dates(&settings, date(2025, 2, 26)).unwrap();
+
+        for day in timetable.iter() {
+            assert_eq!(
+                day.get_timing(TimingName::Taraweeh).is_some(),
+                dates.contains(&day.get_date()),
+                "Taraweeh on {}",
+                day.get_date()
+            );
+        }
+
+        let ramadan = Ramadan::new(year, timetable.iter(), &settings);
+        assert_eq!(ramadan.iter().count(), 29);
+        for (fast, number) in ramadan.iter().zip(1..) {
+            let day = fast.get_day();
+            assert_eq!(fast.number(), number);
+            assert!(dates.contains(&day.get_date()));
+
+            let isha = day.get_prayer(PrayerName::Isha).get_datetime();
+            let taraweeh = day.get_timing(TimingName::Taraweeh).unwrap().get_datetime();
+            assert_eq!(taraweeh - isha, Duration::minutes(15));
+
+            let imsak = day.get_timing(TimingName::Imsak).unwrap();
+            assert_eq!(fast.suhoor_end(), imsak.get_time());
+        }
+    }
+}
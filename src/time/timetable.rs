@@ -0,0 +1,363 @@
+//! Module for holding a [Timetable] of [Prayers](super::prayer::Prayer) over any range of dates
+
+use crate::{
+    argparser::settings::PrayerSettings,
+    core::timing::Timing,
+    types::{PrayerName, Provenance, TimingName},
+};
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use std::ops::RangeInclusive;
+
+use super::{day::Day, zone};
+
+/// Tuple struct containing the [Days](Day) of a range of dates, in order
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timetable(Vec<Day>);
+
+impl Timetable {
+    pub fn new(mut days: Vec<Day>) -> Self {
+        days.sort();
+        days.dedup_by_key(|day| day.get_date());
+        Self(days)
+    }
+
+    /// Joins timetables, keeping the first of any days they share
+    pub fn merge<I: IntoIterator<Item = Timetable>>(timetables: I) -> Self {
+        Self::new(
+            timetables
+                .into_iter()
+                .flat_map(|timetable| timetable.0)
+                .collect(),
+        )
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Day> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the first and last dates of the timetable
+    pub fn range(&self) -> Option<RangeInclusive<NaiveDate>> {
+        let first = self.0.first()?.get_date();
+        let last = self.0.last()?.get_date();
+        Some(first..=last)
+    }
+
+    /// Keeps only the days inside the range
+    pub fn retain_range(&mut self, range: &RangeInclusive<NaiveDate>) {
+        self.0.retain(|day| range.contains(&day.get_date()));
+    }
+
+    /// Checks if any day's times came from a fallback source
+    pub fn is_fallback(&self) -> bool {
+        self.0.iter().any(Day::is_fallback)
+    }
+
+    pub fn update_day(&mut self, day: &Day) {
+        for current_day in self.0.iter_mut() {
+            if day.get_date() == current_day.get_date() {
+                *current_day = day.clone();
+            }
+        }
+    }
+
+    /// Gets the current day at the location of the settings
+    pub fn today(&self, prayer_settings: &PrayerSettings) -> Option<&Day> {
+        self.select_by_day_offset(prayer_settings, 0)
+    }
+
+    pub fn today_mut(&mut self, prayer_settings: &PrayerSettings) -> Option<&mut Day> {
+        let today = prayer_settings.today();
+        self.0.iter_mut().find(|d| d.get_date() == today)
+    }
+
+    pub fn tomorrow(&self, prayer_settings: &PrayerSettings) -> Option<&Day> {
+        self.select_by_day_offset(prayer_settings, 1)
+    }
+
+    /// Gets the day the given number of days after the current day at the location of the settings
+    pub fn select_by_day_offset(
+        &self,
+        prayer_settings: &PrayerSettings,
+        days_offset: i64,
+    ) -> Option<&Day> {
+        self.select_by_date(prayer_settings.today() + Duration::days(days_offset))
+    }
+
+    pub fn select_by_date(&self, date: NaiveDate) -> Option<&Day> {
+        self.0.iter().find(|d| d.get_date() == date)
+    }
+
+    /// Shows every time on the clock of another time zone, such as the viewer's
+    pub fn in_time_zone<Z: TimeZone>(&self, time_zone: &Z) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|day| day.in_time_zone(time_zone))
+                .collect(),
+        )
+    }
+
+    /// Records where the times of every day were obtained
+    pub(crate) fn set_provenance(&mut self, provenance: &Provenance) {
+        for day in self.0.iter_mut() {
+            day.set_provenance(provenance.clone());
+        }
+    }
+
+    /// Marks the times of every day as read back from storage, keeping when they were obtained
+    #[cfg(feature = "cache")]
+    pub(crate) fn mark_cached(&mut self) {
+        for day in self.0.iter_mut() {
+            if let Some(provenance) = day.provenance().cloned() {
+                day.set_provenance(Provenance {
+                    origin: crate::types::Origin::Cached,
+                    ..provenance
+                });
+            }
+        }
+    }
+
+    /// Marks the times of every day as coming from a fallback source
+    pub(crate) fn mark_fallback(&mut self) {
+        for day in self.0.iter_mut() {
+            if let Some(provenance) = day.provenance().cloned() {
+                day.set_provenance(Provenance {
+                    fallback: true,
+                    ..provenance
+                });
+            }
+        }
+    }
+
+    /// Applies the user's offsets and Hijri calendar, then fills in the derived times
+    pub(crate) fn apply_settings(&mut self, prayer_settings: &PrayerSettings) {
+        let offsets = prayer_settings.offsets();
+        for day in self.0.iter_mut() {
+            day.shift_prayers(prayer_settings.time_zone(), |name| offsets.get(name));
+            day.set_hijri_date(prayer_settings.hijri_date(day.get_date()));
+        }
+
+        self.derive_timings(prayer_settings);
+    }
+
+    /// Fills in the times derived from the prayers, sunrise and sunset
+    ///
+    /// Days without a sunrise or sunset are left as they are. The morning after
+    /// the last day is assumed to be a day after the last day's morning.
+    fn derive_timings(&mut self, prayer_settings: &PrayerSettings) {
+        let imsak_minutes = prayer_settings.imsak_minutes();
+        let duha_minutes = prayer_settings.duha_minutes();
+        let time_zone = prayer_settings.time_zone();
+        let in_zone = |time: DateTime<FixedOffset>| zone::from_utc(time_zone, time.naive_utc());
+
+        let mornings = self
+            .0
+            .iter()
+            .filter_map(|day| {
+                let sunrise = day.get_timing(TimingName::Sunrise)?.get_datetime();
+                let fajr = day.get_prayer(PrayerName::Fajr).get_datetime();
+                Some((day.get_date(), fajr, sunrise))
+            })
+            .collect::<Vec<(NaiveDate, DateTime<FixedOffset>, DateTime<FixedOffset>)>>();
+
+        for day in self.0.iter_mut() {
+            let date = day.get_date();
+            let fajr = day.get_prayer(PrayerName::Fajr).get_datetime();
+            let sunrise = match day.get_timing(TimingName::Sunrise) {
+                Some(sunrise) => sunrise.get_datetime(),
+                None => continue,
+            };
+            let sunset = match day.get_timing(TimingName::Sunset) {
+                Some(sunset) => sunset.get_datetime(),
+                None => continue,
+            };
+
+            let tomorrow = date + Duration::days(1);
+            let (next_fajr, next_sunrise) = mornings
+                .iter()
+                .find(|(date, _, _)| *date == tomorrow)
+                .map_or(
+                    (fajr + Duration::days(1), sunrise + Duration::days(1)),
+                    |&(_, fajr, sunrise)| (fajr, sunrise),
+                );
+
+            // Measured between moments, so nights when the clocks change keep their length
+            let night = next_sunrise - sunset;
+            let night_to_fajr = next_fajr - sunset;
+
+            for (name, time) in [
+                (TimingName::Imsak, fajr - Duration::minutes(imsak_minutes)),
+                (TimingName::Duha, sunrise + Duration::minutes(duha_minutes)),
+                (TimingName::Midnight, sunset + night / 2),
+                (TimingName::MidnightToFajr, sunset + night_to_fajr / 2),
+                (TimingName::LastThird, sunset + night_to_fajr * 2 / 3),
+            ] {
+                day.set_timing(Timing::new(name, in_zone(time)));
+            }
+
+            if day.hijri_date().is_ramadan() {
+                let isha = day.get_prayer(PrayerName::Isha).get_datetime();
+                day.set_timing(Timing::new(
+                    TimingName::Taraweeh,
+                    in_zone(isha + Duration::minutes(prayer_settings.taraweeh_minutes())),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        argparser::settings::test_settings, calculator::calculate_timetable, types::Coordinates,
+    };
+
+    use chrono::Utc;
+
+    const LONDON: Coordinates = Coordinates {
+        latitude: 51.5074,
+        longitude: -0.1278,
+    };
+
+    const METHODS: &str = "{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}";
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    /// Calculates the days either side of the date in London
+    fn london(date: NaiveDate) -> Timetable {
+        let settings = test_settings(LONDON, "Europe/London", METHODS);
+        let range = date - Duration::days(1)..=date + Duration::days(1);
+        calculate_timetable(&settings, &range).unwrap()
+    }
+
+    fn timing(day: &Day, name: TimingName) -> DateTime<FixedOffset> {
+        day.get_timing(name).unwrap().get_datetime()
+    }
+
+    fn offset_hours(time: DateTime<FixedOffset>) -> i32 {
+        time.offset().local_minus_utc() / 3600
+    }
+
+    fn assert_near(time: DateTime<FixedOffset>, expected: DateTime<FixedOffset>) {
+        assert!(
+            (time - expected).num_seconds().abs() <= 1,
+            "{} is not {}",
+            time,
+            expected
+        );
+    }
+
+    #[test]
+    fn moves_the_prayers_with_the_clocks() {
+        for (date, before, after) in [(date(2026, 3, 29), 0, 1), (date(2026, 10, 25), 1, 0)] {
+            let timetable = london(date);
+            let yesterday = timetable.select_by_date(date - Duration::days(1)).unwrap();
+            let today = timetable.select_by_date(date).unwrap();
+
+            for (prayer, next) in yesterday.get_prayers().iter().zip(today.get_prayers()) {
+                assert_eq!(offset_hours(prayer.get_datetime()), before);
+                assert_eq!(offset_hours(next.get_datetime()), after);
+
+                // The sun moves by minutes a day, the clocks by an hour
+                let moved = (next.get_time() - prayer.get_time()).num_minutes();
+                let expected = (after - before) as i64 * 60;
+                assert!(
+                    (moved - expected).abs() <= 5,
+                    "{:?} moved by {} minutes on {}",
+                    prayer.get_name(),
+                    moved,
+                    date
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn measures_the_nights_the_clocks_change() {
+        // Clocks going forward show the night an hour longer than it lasts, and back shorter
+        for (date, clock_hours, midnight, last_third) in [
+            (date(2026, 3, 28), 1, "00:05 +00:00", "00:53 +00:00"),
+            (date(2026, 10, 24), -1, "00:45 +01:00", "01:49 +01:00"),
+        ] {
+            let timetable = london(date);
+            let day = timetable.select_by_date(date).unwrap();
+            let next_day = timetable.select_by_date(date + Duration::days(1)).unwrap();
+
+            let sunset = timing(day, TimingName::Sunset);
+            let sunrise = timing(next_day, TimingName::Sunrise);
+            let fajr = next_day.get_prayer(PrayerName::Fajr).get_datetime();
+            let shown = sunrise.naive_local() - sunset.naive_local();
+            assert_eq!((shown - (sunrise - sunset)).num_hours(), clock_hours);
+
+            let time = timing(day, TimingName::Midnight);
+            assert_near(time, sunset + (sunrise - sunset) / 2);
+            assert_eq!(time.format("%H:%M %:z").to_string(), midnight);
+
+            let time = timing(day, TimingName::MidnightToFajr);
+            assert_near(time, sunset + (fajr - sunset) / 2);
+
+            let time = timing(day, TimingName::LastThird);
+            assert_near(time, sunset + (fajr - sunset) * 2 / 3);
+            assert_eq!(time.format("%H:%M %:z").to_string(), last_third);
+        }
+    }
+
+    #[test]
+    fn shows_a_day_on_another_clock() {
+        // New York keeps summer time for a week after London
+        let timetable = london(date(2026, 10, 25));
+        let day = timetable.select_by_date(date(2026, 10, 25)).unwrap();
+
+        let shown = day.in_time_zone(&chrono_tz::America::New_York);
+
+        assert_eq!(shown.get_date(), day.get_date());
+        for (prayer, shown) in day.get_prayers().iter().zip(shown.get_prayers()) {
+            assert_eq!(shown.get_datetime(), prayer.get_datetime());
+            assert_eq!(offset_hours(shown.get_datetime()), -4);
+            assert_eq!((prayer.get_time() - shown.get_time()).num_hours(), 4);
+        }
+        for (timing, shown) in day.get_timings().iter().zip(shown.get_timings()) {
+            assert_eq!(shown.get_name(), timing.get_name());
+            assert_eq!(shown.get_datetime(), timing.get_datetime());
+            assert_eq!(offset_hours(shown.get_datetime()), -4);
+        }
+    }
+
+    #[test]
+    fn finds_today_at_the_location() {
+        // Fourteen hours ahead of UTC, so the date there is mostly a day later
+        let settings = test_settings(
+            Coordinates {
+                latitude: 1.8721,
+                longitude: -157.4278,
+            },
+            "Pacific/Kiritimati",
+            METHODS,
+        );
+        let today = (Utc::now() + Duration::hours(14)).naive_utc().date();
+        let range = today - Duration::days(2)..=today + Duration::days(2);
+        let timetable = calculate_timetable(&settings, &range).unwrap();
+
+        assert_eq!(settings.today(), today);
+        assert_eq!(timetable.today(&settings).map(Day::get_date), Some(today));
+        assert_eq!(
+            timetable.tomorrow(&settings).map(Day::get_date),
+            Some(today + Duration::days(1))
+        );
+    }
+}
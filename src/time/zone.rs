@@ -0,0 +1,107 @@
+//! Conversions into the time zone of a location
+//!
+//! Locations without a time zone use the system's zone.
+
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, Offset,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+/// Gets the moment in the zone at the given UTC time
+pub(crate) fn from_utc(time_zone: Option<Tz>, utc: NaiveDateTime) -> DateTime<FixedOffset> {
+    match time_zone {
+        Some(time_zone) => fix(time_zone.from_utc_datetime(&utc)),
+        None => fix(Local.from_utc_datetime(&utc)),
+    }
+}
+
+/// Gets the moment in the zone at the given wall-clock time
+///
+/// Times skipped when the clocks go forward are moved forward by the gap,
+/// and times repeated when the clocks go back are taken before the change.
+pub(crate) fn from_local(time_zone: Option<Tz>, local: NaiveDateTime) -> DateTime<FixedOffset> {
+    match time_zone {
+        Some(time_zone) => resolve(&time_zone, local),
+        None => resolve(&Local, local),
+    }
+}
+
+/// Gets the current date in the zone
+pub(crate) fn today(time_zone: Option<Tz>) -> NaiveDate {
+    from_utc(time_zone, Utc::now().naive_utc())
+        .date()
+        .naive_local()
+}
+
+/// Converts a moment into another zone, keeping only the offset
+pub(crate) fn fix<Z: TimeZone>(time: DateTime<Z>) -> DateTime<FixedOffset> {
+    let offset = time.offset().fix();
+    time.with_timezone(&offset)
+}
+
+fn resolve<Z: TimeZone>(zone: &Z, local: NaiveDateTime) -> DateTime<FixedOffset> {
+    match zone.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => fix(time),
+        LocalResult::None => {
+            // Read the wall-clock time using the offset from before the clocks changed
+            let offset = zone
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            fix(zone.from_utc_datetime(&(local - offset)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono_tz::Europe::London;
+
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2026, month, day).and_hms(hour, minute, 0)
+    }
+
+    fn offset_hours(time: DateTime<FixedOffset>) -> i32 {
+        time.offset().local_minus_utc() / 3600
+    }
+
+    #[test]
+    fn moves_skipped_times_forward() {
+        let before = from_local(Some(London), at(3, 29, 0, 30));
+        assert_eq!(before.naive_local(), at(3, 29, 0, 30));
+        assert_eq!(offset_hours(before), 0);
+
+        let skipped = from_local(Some(London), at(3, 29, 1, 30));
+        assert_eq!(skipped.naive_local(), at(3, 29, 2, 30));
+        assert_eq!(offset_hours(skipped), 1);
+        assert_eq!(skipped - before, Duration::hours(1));
+    }
+
+    #[test]
+    fn takes_repeated_times_before_the_change() {
+        let repeated = from_local(Some(London), at(10, 25, 1, 30));
+        assert_eq!(repeated.naive_local(), at(10, 25, 1, 30));
+        assert_eq!(repeated.naive_utc(), at(10, 25, 0, 30));
+        assert_eq!(offset_hours(repeated), 1);
+
+        let after = from_local(Some(London), at(10, 25, 2, 30));
+        assert_eq!(offset_hours(after), 0);
+        assert_eq!(after - repeated, Duration::hours(2));
+    }
+
+    #[test]
+    fn shows_moments_on_the_clock_of_the_day() {
+        for (utc, local, offset) in [
+            (at(3, 29, 0, 59), at(3, 29, 0, 59), 0),
+            (at(3, 29, 1, 0), at(3, 29, 2, 0), 1),
+            (at(10, 25, 0, 59), at(10, 25, 1, 59), 1),
+            (at(10, 25, 1, 0), at(10, 25, 1, 0), 0),
+        ] {
+            let time = from_utc(Some(London), utc);
+            assert_eq!(time.naive_local(), local, "{} UTC", utc);
+            assert_eq!(offset_hours(time), offset, "{} UTC", utc);
+        }
+    }
+}
@@ -1,15 +1,20 @@
+use chrono::{DateTime, Utc};
+#[cfg(feature = "cli")]
 use clap::ArgEnum;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use std::{error, fmt, io};
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
 
 /// Wrapper around [Result]
 pub type UmmahResult<T> = Result<T, UmmahError>;
 
-/// Names for all the prayers
+/// Names for all the obligatory prayers
 ///
-/// TODO: Add support for Taraweeh and Tahajjud.
+/// Taraweeh is not obligatory, so it is kept with the [other times](TimingName::Taraweeh).
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum PrayerName {
     Fajr,
@@ -31,56 +36,671 @@ impl fmt::Display for PrayerName {
     }
 }
 
+/// Names for the other times of the day
+///
+/// These are kept apart from the obligatory [prayers](PrayerName).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimingName {
+    /// Start of the fast, shortly before Fajr
+    Imsak,
+    Sunrise,
+    /// Start of the forenoon prayer, shortly after sunrise
+    Duha,
+    Sunset,
+    /// Start of the night prayer in Ramadan, some time after Isha
+    Taraweeh,
+    /// Halfway between sunset and sunrise
+    Midnight,
+    /// Halfway between sunset and Fajr
+    MidnightToFajr,
+    /// Start of the last third of the night, the preferred time for Tahajjud
+    LastThird,
+}
+
+impl fmt::Display for TimingName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimingName::Imsak => write!(f, "Imsak"),
+            TimingName::Sunrise => write!(f, "Sunrise"),
+            TimingName::Duha => write!(f, "Duha"),
+            TimingName::Sunset => write!(f, "Sunset"),
+            TimingName::Taraweeh => write!(f, "Taraweeh"),
+            TimingName::Midnight => write!(f, "Midnight"),
+            TimingName::MidnightToFajr => write!(f, "Midnight (Fajr)"),
+            TimingName::LastThird => write!(f, "Last third"),
+        }
+    }
+}
+
+/// Geographical position of a location, in degrees
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct Coordinates {
+    /// Degrees north of the equator
+    pub latitude: f64,
+    /// Degrees east of the prime meridian
+    pub longitude: f64,
+}
+
+/// Mean radius of the Earth, in kilometres
+const EARTH_RADIUS: f64 = 6371.0;
+
+impl Coordinates {
+    /// Great-circle distance to another location, in kilometres
+    pub fn distance_to(&self, other: Coordinates) -> f64 {
+        let (latitude, other_latitude) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let longitude_difference = (other.longitude - self.longitude).to_radians();
+
+        let haversine = ((other_latitude - latitude) / 2.0).sin().powi(2)
+            + latitude.cos() * other_latitude.cos() * (longitude_difference / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS * haversine.sqrt().asin()
+    }
+}
+
 /// The method to determine the height of the sun
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ArgEnum)]
+///
+/// Used when Fajr or Isha fall too far into the night, or do not occur at all.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
 pub enum LatitudeMethod {
-    OneSeventh = 3,
+    /// Limits the twilight to a seventh of the night
+    OneSeventh,
+    /// Limits the twilight to a sixtieth of the night for every degree of the angle
     AngleBased,
+    /// Limits the twilight to half of the night
+    MiddleOfNight,
+    /// Uses the times of the nearest latitude where the twilight occurs
+    NearestLatitude,
+    /// Uses the times of the last day where the twilight occurred
+    NearestDay,
+    /// Chooses a rule from the latitude on days where the twilight does not occur
+    Auto,
+}
+
+impl fmt::Display for LatitudeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatitudeMethod::OneSeventh => write!(f, "one-seventh"),
+            LatitudeMethod::AngleBased => write!(f, "angle-based"),
+            LatitudeMethod::MiddleOfNight => write!(f, "middle of the night"),
+            LatitudeMethod::NearestLatitude => write!(f, "nearest latitude"),
+            LatitudeMethod::NearestDay => write!(f, "nearest day"),
+            LatitudeMethod::Auto => write!(f, "automatic"),
+        }
+    }
+}
+
+impl LatitudeMethod {
+    /// Identifier used by www.salahtimes.com, if the website supports the method
+    #[cfg(feature = "network")]
+    pub(crate) fn salahtimes_id(&self) -> Option<u8> {
+        match self {
+            LatitudeMethod::OneSeventh => Some(3),
+            LatitudeMethod::AngleBased => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Identifier used by APIs in the style of api.aladhan.com, if they support the method
+    #[cfg(feature = "network")]
+    pub(crate) fn aladhan_id(&self) -> Option<u8> {
+        match self {
+            LatitudeMethod::MiddleOfNight => Some(1),
+            LatitudeMethod::OneSeventh => Some(2),
+            LatitudeMethod::AngleBased => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Portion of the night used as the limit for a time set by an angle
+    ///
+    /// Returns [None] for rules which do not limit the twilight to the night.
+    pub(crate) fn night_portion(&self, angle: f64) -> Option<f64> {
+        match self {
+            LatitudeMethod::OneSeventh => Some(1.0 / 7.0),
+            LatitudeMethod::AngleBased => Some(angle / 60.0),
+            LatitudeMethod::MiddleOfNight => Some(0.5),
+            _ => None,
+        }
+    }
+
+    /// Rule used by [LatitudeMethod::Auto] at the given latitude
+    pub(crate) fn for_latitude(latitude: f64) -> Self {
+        match latitude.abs() {
+            latitude if latitude < 48.0 => LatitudeMethod::AngleBased,
+            latitude if latitude < 55.0 => LatitudeMethod::OneSeventh,
+            _ => LatitudeMethod::NearestLatitude,
+        }
+    }
+}
+
+/// Records the prayers of a day moved by a high latitude rule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LatitudeAdjustment {
+    /// The rule used to move the prayers
+    pub rule: LatitudeMethod,
+    /// The prayers which were moved
+    pub prayers: Vec<PrayerName>,
+}
+
+impl fmt::Display for LatitudeAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prayers = self
+            .prayers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        write!(f, "{} adjusted using the {} rule", prayers, self.rule)
+    }
+}
+
+/// How the times of a day were obtained
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Origin {
+    /// Downloaded earlier and read from the cache
+    Cached,
+    /// Downloaded for this request
+    Downloaded,
+    /// Read from a local timetable
+    File,
+    /// Calculated locally
+    Calculated,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Cached => write!(f, "Cached download"),
+            Origin::Downloaded => write!(f, "Fresh download"),
+            Origin::File => write!(f, "Read from file"),
+            Origin::Calculated => write!(f, "Calculated"),
+        }
+    }
+}
+
+/// Records where and when the times of a day were obtained
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub origin: Origin,
+    /// Name and version of the source, as given by [TimetableSource::provider](crate::source::TimetableSource::provider)
+    pub provider: String,
+    /// The moment the times were downloaded, read or calculated
+    pub obtained: DateTime<Utc>,
+    /// Whether the times came from a later source after the ones before it failed
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+impl Provenance {
+    pub(crate) fn new(origin: Origin, provider: String) -> Self {
+        Self {
+            origin,
+            provider,
+            obtained: Utc::now(),
+            fallback: false,
+        }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} by {} at {}",
+            self.origin,
+            self.provider,
+            self.obtained.format("%d %b %Y %H:%M UTC")
+        )?;
+        if self.fallback {
+            write!(f, ", as the preferred sources failed")?;
+        }
+        Ok(())
+    }
 }
 
 /// The organisation to base the calculations from
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ArgEnum)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
 pub enum PrayerMethod {
     /// Muslim World League
-    MWL = 1,
-    /// University of Islamic Sciences
-    UIS = 3,
+    MWL,
+    /// University of Islamic Sciences, Karachi
+    UIS,
     /// Islamic Society of North America
-    ISNA = 5,
+    ISNA,
+    /// Egyptian General Authority of Survey
+    Egyptian,
+    /// Umm al-Qura University, Makkah
+    UmmAlQura,
+    /// Institute of Geophysics, University of Tehran
+    Tehran,
+    /// Gulf Region
+    Gulf,
+    /// Kuwait
+    Kuwait,
+    /// Qatar
+    Qatar,
+    /// Majlis Ugama Islam Singapura
+    Singapore,
+    /// Diyanet İşleri Başkanlığı, Turkey
+    Turkey,
+    /// Union des Organisations Islamiques de France
+    UOIF,
+    /// Moonsighting Committee Worldwide
+    MoonsightingCommittee,
+    /// User-defined angles and intervals
+    Custom,
+}
+
+impl PrayerMethod {
+    /// Identifier used by www.salahtimes.com, if the website supports the method
+    #[cfg(feature = "network")]
+    pub(crate) fn salahtimes_id(&self) -> Option<u8> {
+        match self {
+            PrayerMethod::MWL => Some(1),
+            PrayerMethod::UIS => Some(3),
+            PrayerMethod::ISNA => Some(5),
+            _ => None,
+        }
+    }
+
+    /// Identifier used by APIs in the style of api.aladhan.com, if they support the method
+    #[cfg(feature = "network")]
+    pub(crate) fn aladhan_id(&self) -> Option<u8> {
+        match self {
+            PrayerMethod::UIS => Some(1),
+            PrayerMethod::ISNA => Some(2),
+            PrayerMethod::MWL => Some(3),
+            PrayerMethod::UmmAlQura => Some(4),
+            PrayerMethod::Egyptian => Some(5),
+            PrayerMethod::Tehran => Some(7),
+            PrayerMethod::Gulf => Some(8),
+            PrayerMethod::Kuwait => Some(9),
+            PrayerMethod::Qatar => Some(10),
+            PrayerMethod::Singapore => Some(11),
+            PrayerMethod::UOIF => Some(12),
+            PrayerMethod::Turkey => Some(13),
+            PrayerMethod::MoonsightingCommittee => Some(15),
+            PrayerMethod::Custom => None,
+        }
+    }
+
+    /// Angles and intervals used by the method
+    ///
+    /// Returns [None] for [PrayerMethod::Custom], whose parameters are chosen by the user.
+    pub fn parameters(&self) -> Option<MethodParameters> {
+        let parameters = match self {
+            PrayerMethod::MWL => MethodParameters::angles(18.0, 17.0),
+            PrayerMethod::UIS => MethodParameters::angles(18.0, 18.0),
+            PrayerMethod::ISNA => MethodParameters::angles(15.0, 15.0),
+            PrayerMethod::Egyptian => MethodParameters::angles(19.5, 17.5),
+            PrayerMethod::UmmAlQura => MethodParameters {
+                isha: Twilight::Minutes(90),
+                isha_in_ramadan: Some(Twilight::Minutes(120)),
+                ..MethodParameters::angles(18.5, 0.0)
+            },
+            PrayerMethod::Tehran => MethodParameters {
+                maghrib: Twilight::Angle(4.5),
+                ..MethodParameters::angles(17.7, 14.0)
+            },
+            PrayerMethod::Gulf => MethodParameters {
+                isha: Twilight::Minutes(90),
+                ..MethodParameters::angles(19.5, 0.0)
+            },
+            PrayerMethod::Kuwait => MethodParameters::angles(18.0, 17.5),
+            PrayerMethod::Qatar => MethodParameters {
+                isha: Twilight::Minutes(90),
+                ..MethodParameters::angles(18.0, 0.0)
+            },
+            PrayerMethod::Singapore => MethodParameters::angles(20.0, 18.0),
+            PrayerMethod::Turkey => MethodParameters::angles(18.0, 17.0),
+            PrayerMethod::UOIF => MethodParameters::angles(12.0, 12.0),
+            PrayerMethod::MoonsightingCommittee => MethodParameters {
+                fajr: Twilight::Seasonal(18.0),
+                dhuhr: 5,
+                maghrib: Twilight::Minutes(3),
+                isha: Twilight::Seasonal(18.0),
+                ..MethodParameters::angles(18.0, 18.0)
+            },
+            PrayerMethod::Custom => return None,
+        };
+
+        Some(parameters)
+    }
+}
+
+/// Describes when a twilight-dependent prayer begins
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum Twilight {
+    /// The sun is the given number of degrees below the horizon
+    Angle(f64),
+    /// A fixed number of minutes before sunrise for Fajr,
+    /// after sunset for Maghrib, or after Maghrib for Isha
+    Minutes(i64),
+    /// The sun is the given number of degrees below the horizon,
+    /// limited by the Moonsighting Committee's seasonal intervals
+    Seasonal(f64),
+}
+
+/// Parameters used to calculate Fajr, Dhuhr, Maghrib and Isha
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct MethodParameters {
+    /// Start of Fajr
+    pub fajr: Twilight,
+    /// Minutes after the sun's transit at which Dhuhr begins
+    #[serde(default)]
+    pub dhuhr: i64,
+    /// Start of Maghrib
+    pub maghrib: Twilight,
+    /// Start of Isha
+    pub isha: Twilight,
+    /// Start of Isha during Ramadan, if different from the rest of the year
+    pub isha_in_ramadan: Option<Twilight>,
+}
+
+impl MethodParameters {
+    /// Parameters for methods using angles for Fajr and Isha and sunset for Maghrib
+    pub const fn angles(fajr: f64, isha: f64) -> Self {
+        Self {
+            fajr: Twilight::Angle(fajr),
+            dhuhr: 0,
+            maghrib: Twilight::Minutes(0),
+            isha: Twilight::Angle(isha),
+            isha_in_ramadan: None,
+        }
+    }
 }
 
 /// The school of thought to follow for the afternoon prayer
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, ArgEnum)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
 pub enum AsrMethod {
     Shafi = 1,
     Hanafi,
 }
 
+impl AsrMethod {
+    /// School used by APIs in the style of api.aladhan.com
+    #[cfg(feature = "network")]
+    pub(crate) fn aladhan_id(&self) -> u8 {
+        match self {
+            AsrMethod::Shafi => 0,
+            AsrMethod::Hanafi => 1,
+        }
+    }
+
+    /// Length of an object's shadow, relative to its height, at the start of Asr
+    pub(crate) fn shadow_length(&self) -> f64 {
+        match self {
+            AsrMethod::Shafi => 1.0,
+            AsrMethod::Hanafi => 2.0,
+        }
+    }
+}
+
+/// The calendar used to find Hijri dates
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "cli", derive(ArgEnum))]
+pub enum HijriCalendar {
+    /// Arithmetical calendar, as used by the Kuwaiti algorithm
+    Tabular,
+    /// Official calendar of Saudi Arabia
+    UmmAlQura,
+}
+
 /// Represents all possible program errors
+///
+/// Each error names the URL, file, row or column it concerns, and keeps the
+/// error that caused it as its [source](std::error::Error::source).
+/// Use [UmmahError::kind] to tell the broad kinds of error apart.
 #[derive(Debug, Error)]
 pub enum UmmahError {
-    /// Thrown when obtaining prayer time
-    #[error("Cannot get new time")]
-    Prayer,
+    /// Thrown when calculating times for a location without coordinates
+    #[error("Location has no coordinates")]
+    Coordinates,
+
+    /// Thrown when a city is not in the gazetteer
+    #[error("Unknown city {city:?}{}", suggestion_hint(.suggestions))]
+    City {
+        city: String,
+        suggestions: Vec<String>,
+    },
+
+    /// Thrown when the location cannot exist, such as a latitude beyond the poles
+    #[error("Invalid location: {reason}")]
+    Location { reason: String },
+
+    /// Thrown when none of the timetable sources in the settings can give times for them
+    #[error("No timetable source supports the location and methods")]
+    Source,
+
+    /// Thrown when a default in the config file is for no argument or is not valid for it
+    #[error("Invalid default for {key:?}: {reason}")]
+    Config { key: String, reason: String },
+
+    /// Thrown when the calculation method has no parameters to calculate with
+    #[error("No parameters set for the calculation method")]
+    Parameters,
+
+    /// Thrown when the proxy in the settings is not a valid URL
+    #[cfg(feature = "network")]
+    #[error("Invalid proxy {proxy:?}")]
+    Proxy {
+        proxy: String,
+        source: reqwest::Error,
+    },
+
+    /// Thrown when asking for times between dates that are out of order
+    #[error("Start date {start} is after end date {end}")]
+    EmptyRange {
+        start: chrono::NaiveDate,
+        end: chrono::NaiveDate,
+    },
 
-    /// Thrown when parsing CSV file
-    #[error("Failed to read CSV file")]
-    CSV(#[from] csv::Error),
+    /// Thrown when the HTTP client cannot be set up
+    #[cfg(feature = "network")]
+    #[error("Failed to set up the HTTP client")]
+    Client(#[source] reqwest::Error),
 
-    /// Thrown when parsing timestamps
-    #[error("Failed to parse time")]
-    DateTime(#[from] chrono::ParseError),
+    /// Thrown when a request cannot be sent or its answer cannot be read
+    #[cfg(feature = "network")]
+    #[error("Failed to request {url}")]
+    Network { url: String, source: reqwest::Error },
 
-    /// Thrown on file/IO errors
-    #[error("Failed to handle filesystem/IO")]
-    IO(#[from] io::Error),
+    /// Thrown when a server answers a request with an error status
+    #[cfg(feature = "network")]
+    #[error("{url} answered with HTTP status {status}")]
+    Status { url: String, status: u16 },
 
-    /// Thrown on (de)serialization errors
-    #[error("Failed to (de)serialize")]
-    Serde(#[from] serde_yaml::Error),
+    /// Thrown when a server answers with something other than the timetable expected
+    #[cfg(feature = "network")]
+    #[error("Unexpected answer from {url}: {reason}")]
+    Response { url: String, reason: String },
 
-    /// Thrown when attempting to submit request to website
-    #[error("Failed to request times")]
-    Unknown(#[from] Box<dyn error::Error>),
+    /// Thrown when a server's JSON answer does not hold the expected fields
+    #[cfg(feature = "network")]
+    #[error("Failed to read the answer from {url}")]
+    Json {
+        url: String,
+        source: serde_json::Error,
+    },
+
+    /// Thrown when a row of a CSV timetable cannot be read
+    #[error("Failed to read row {row}{} of the CSV timetable", column_hint(.column))]
+    Csv {
+        row: usize,
+        column: Option<String>,
+        source: csv::Error,
+    },
+
+    /// Thrown when a date or time in a timetable cannot be read or is out of order
+    #[error("Invalid {column} {value:?} in row {row}: {reason}")]
+    Timetable {
+        row: usize,
+        column: &'static str,
+        value: String,
+        reason: String,
+    },
+
+    /// Thrown when a date in a timetable is outside the requested dates
+    #[error("Date {date:?} in row {row} is outside the requested dates {start} to {end}")]
+    DateRange {
+        row: usize,
+        date: String,
+        start: chrono::NaiveDate,
+        end: chrono::NaiveDate,
+    },
+
+    /// Thrown when a timetable has no days
+    #[error("Timetable has no days")]
+    EmptyTimetable,
+
+    /// Thrown when a timetable does not have the day asked for
+    #[error("Timetable has no times for {date}")]
+    MissingDate { date: chrono::NaiveDate },
+
+    /// Thrown when a prayer time cannot be calculated, as when the sun does not reach its angle
+    #[error("Cannot calculate the prayer times on {date}")]
+    Prayer { date: chrono::NaiveDate },
+
+    /// Thrown when no Ramadan is found in the year after a date
+    #[error("No Ramadan found in the year from {date}")]
+    Ramadan { date: chrono::NaiveDate },
+
+    /// Thrown when the cached timetable is missing or does not cover the day
+    #[error("No up-to-date timetable is cached at {}", .path.display())]
+    StaleCache { path: PathBuf },
+
+    /// Thrown when a file or directory cannot be read or written
+    #[error("Failed to access {}", .path.display())]
+    File { path: PathBuf, source: io::Error },
+
+    /// Thrown when a file cannot be (de)serialized
+    #[cfg(feature = "cache")]
+    #[error("Failed to (de)serialize {}", .path.display())]
+    Yaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    /// Thrown when the runtime for the blocking functions cannot be started
+    #[cfg(feature = "network")]
+    #[error("Failed to start the async runtime")]
+    Runtime(#[source] io::Error),
+
+    /// Thrown when progress cannot be shown on the terminal
+    #[error("Failed to write to the terminal")]
+    Terminal(#[source] io::Error),
+
+    /// Thrown when the HTML timetable cannot be written
+    #[error("Failed to write the HTML timetable")]
+    Html(#[from] fmt::Error),
+}
+
+/// Broad kinds of [UmmahError], for callers that act on the kind rather than the details
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The location is unknown, invalid or has no coordinates
+    Location,
+    /// The settings or requested dates cannot be used
+    Settings,
+    /// A server could not be reached or its answer could not be read
+    Network,
+    /// A server answered with an error status
+    Http,
+    /// A timetable or answer could not be parsed
+    Parse,
+    /// A timetable date is outside the requested dates
+    Range,
+    /// No times could be calculated
+    Calculation,
+    /// The cached timetable is missing or out of date
+    Cache,
+    /// A file or the terminal could not be read or written
+    Io,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Location => write!(f, "location"),
+            ErrorKind::Settings => write!(f, "settings"),
+            ErrorKind::Network => write!(f, "network"),
+            ErrorKind::Http => write!(f, "http"),
+            ErrorKind::Parse => write!(f, "parse"),
+            ErrorKind::Range => write!(f, "range"),
+            ErrorKind::Calculation => write!(f, "calculation"),
+            ErrorKind::Cache => write!(f, "cache"),
+            ErrorKind::Io => write!(f, "io"),
+        }
+    }
+}
+
+impl UmmahError {
+    /// Gets the broad kind of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            UmmahError::Coordinates | UmmahError::City { .. } | UmmahError::Location { .. } => {
+                ErrorKind::Location
+            }
+            UmmahError::Source
+            | UmmahError::Config { .. }
+            | UmmahError::Parameters
+            | UmmahError::EmptyRange { .. } => ErrorKind::Settings,
+            #[cfg(feature = "network")]
+            UmmahError::Proxy { .. } => ErrorKind::Settings,
+            #[cfg(feature = "network")]
+            UmmahError::Client(_) | UmmahError::Network { .. } => ErrorKind::Network,
+            #[cfg(feature = "network")]
+            UmmahError::Status { .. } => ErrorKind::Http,
+            #[cfg(feature = "network")]
+            UmmahError::Response { .. } | UmmahError::Json { .. } => ErrorKind::Parse,
+            UmmahError::Csv { .. } | UmmahError::Timetable { .. } => ErrorKind::Parse,
+            UmmahError::DateRange { .. }
+            | UmmahError::EmptyTimetable
+            | UmmahError::MissingDate { .. } => ErrorKind::Range,
+            UmmahError::Prayer { .. } | UmmahError::Ramadan { .. } => ErrorKind::Calculation,
+            UmmahError::StaleCache { .. } => ErrorKind::Cache,
+            #[cfg(feature = "cache")]
+            UmmahError::Yaml { .. } => ErrorKind::Io,
+            #[cfg(feature = "network")]
+            UmmahError::Runtime(_) => ErrorKind::Io,
+            UmmahError::File { .. } | UmmahError::Terminal(_) | UmmahError::Html(_) => {
+                ErrorKind::Io
+            }
+        }
+    }
+
+    /// Wraps an IO error with the path it concerns
+    pub(crate) fn file<P: AsRef<Path>>(path: P) -> impl FnOnce(io::Error) -> Self {
+        let path = path.as_ref().to_owned();
+        move |source| UmmahError::File { path, source }
+    }
+
+    /// Wraps a (de)serialization error with the path it concerns
+    #[cfg(feature = "cache")]
+    pub(crate) fn yaml<P: AsRef<Path>>(path: P) -> impl FnOnce(serde_yaml::Error) -> Self {
+        let path = path.as_ref().to_owned();
+        move |source| UmmahError::Yaml { path, source }
+    }
+}
+
+/// Names the column of a CSV error, if known
+fn column_hint(column: &Option<String>) -> String {
+    column
+        .as_ref()
+        .map_or_else(String::new, |column| format!(", column {:?}", column))
+}
+
+/// Lists the places a misspelt city may have meant
+fn suggestion_hint(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(", did you mean {}?", suggestions.join(" or "))
+    }
 }
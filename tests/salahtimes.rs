@@ -0,0 +1,115 @@
+//! Compares the calculated times with timetables in the CSV format of www.salahtimes.com
+//!
+//! Each fixture is a month in a UK city with the website's default methods, named
+//! `<city>-<year>-<month>.csv`, in winter, at the equinox and in summer. The committed
+//! fixtures were written by `praytimes.py`, a separate implementation of the
+//! PrayTimes.org algorithm, and `make fixtures` replaces them with the website's own.
+
+use chrono::NaiveDate;
+use chrono_tz::Europe::London;
+use ummah::{
+    argparser::settings::PrayerSettings, calculator::calculate_timetable,
+    request_parser::parse_csv_file,
+};
+
+use std::{fs, path::Path};
+
+static FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/salahtimes");
+
+/// Largest difference allowed between a calculated and a downloaded time
+const TOLERANCE_MINUTES: i64 = 1;
+
+/// Settings the website uses by default for a city, at sea level as it does
+fn settings(city: &str) -> PrayerSettings {
+    let settings = format!(
+        "methods: {{latitude: OneSeventh, prayer: MWL, asr: Shafi, sea_level: true}}\n\
+         location: {{country: uk, city: {}, time_zone: Europe/London}}\n\
+         is_audio_downloaded: false\n",
+        city
+    );
+
+    serde_yaml::from_str::<PrayerSettings>(&settings)
+        .expect("Reading settings")
+        .resolve()
+        .expect("Resolving city")
+}
+
+/// Splits a fixture's name into its city and the dates of its month
+fn fixture_month(name: &str) -> (String, std::ops::RangeInclusive<NaiveDate>) {
+    let mut parts = name.trim_end_matches(".csv").rsplitn(3, '-');
+    let month = parts.next().and_then(|month| month.parse().ok());
+    let year = parts.next().and_then(|year| year.parse().ok());
+    let city = parts.next();
+
+    let (city, year, month) = match (city, year, month) {
+        (Some(city), Some(year), Some(month)) => (city, year, month),
+        _ => panic!("Fixture {} is not named <city>-<year>-<month>.csv", name),
+    };
+
+    let start = NaiveDate::from_ymd(year, month, 1);
+    let end = match month {
+        12 => NaiveDate::from_ymd(year + 1, 1, 1),
+        _ => NaiveDate::from_ymd(year, month + 1, 1),
+    }
+    .pred();
+
+    (city.to_owned(), start..=end)
+}
+
+#[test]
+fn calculated_times_match_salahtimes() {
+    let fixtures = Path::new(FIXTURES);
+    let mut names = fs::read_dir(fixtures)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.ends_with(".csv"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    assert!(
+        !names.is_empty(),
+        "No fixtures in {}, save them with `make fixtures`",
+        FIXTURES
+    );
+
+    let mut differences = Vec::new();
+    for name in &names {
+        let (city, range) = fixture_month(name);
+        let settings = settings(&city);
+
+        let data = fs::read(fixtures.join(name)).expect("Reading fixture");
+        let downloaded = parse_csv_file(&data, Some(London), &range).expect("Parsing fixture");
+        let calculated = calculate_timetable(&settings, &range).expect("Calculating times");
+
+        assert_eq!(downloaded.len(), calculated.len(), "Days in {}", name);
+
+        for (downloaded, calculated) in downloaded.iter().zip(calculated.iter()) {
+            let prayers = downloaded.get_prayers();
+            for (downloaded, calculated) in prayers.iter().zip(calculated.get_prayers().iter()) {
+                let difference = calculated.get_datetime() - downloaded.get_datetime();
+                if difference.num_minutes().abs() > TOLERANCE_MINUTES {
+                    differences.push(format!(
+                        "{} {} {:?}: calculated {}, downloaded {}",
+                        city,
+                        downloaded.get_datetime().date().naive_local(),
+                        downloaded.get_name(),
+                        calculated.get_time(),
+                        downloaded.get_time(),
+                    ));
+                }
+            }
+        }
+    }
+
+    assert!(
+        differences.is_empty(),
+        "{} times differ by more than {} minute:\n{}",
+        differences.len(),
+        TOLERANCE_MINUTES,
+        differences.join("\n")
+    );
+}